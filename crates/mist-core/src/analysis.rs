@@ -0,0 +1,389 @@
+//! Numbers about a run that can be shown next to the splits, computed from a [`Run`] and the state of the current
+//! attempt.
+//!
+//! Times are in milliseconds and use the segment times of the given [`TimingMethod`]. A time of 0 in the run means
+//! the time is not known, as everywhere else in mist.
+use crate::timer::{AttemptEnd, Comparison, Run, TimingMethod};
+
+/// Get the sum of the best times of every segment.
+pub fn sum_of_best(run: &Run, method: TimingMethod) -> u128 {
+    run.gold_times_for(method).iter().sum()
+}
+
+/// Get the time the current attempt will finish with if the rest of it goes like the personal best, or like the best
+/// segments if `golds` is true.
+///
+/// `current` is the index of the split being run and `time` is the time of the attempt. Returns None if the personal
+/// best has no time for the current split.
+pub fn pace(
+    run: &Run,
+    method: TimingMethod,
+    current: usize,
+    time: u128,
+    golds: bool,
+) -> Option<u128> {
+    if run.pb_times_for(method).get(current).copied().unwrap_or(0) == 0 {
+        return None;
+    }
+    let times = if golds {
+        run.gold_times_for(method)
+    } else {
+        run.pb_times_for(method)
+    };
+    Some(time + times.iter().skip(current + 1).sum::<u128>())
+}
+
+/// Get how far the time spent on the current split is ahead (negative) or behind (positive) its time in the personal
+/// best, or its best time if `golds` is true.
+///
+/// Returns None if the personal best has no time for the current split, and for runs with only one split, where this
+/// would be the same as the timer.
+pub fn split_diff(
+    run: &Run,
+    method: TimingMethod,
+    current: usize,
+    split_time: u128,
+    golds: bool,
+) -> Option<i128> {
+    let pb = run.pb_times_for(method).get(current).copied().unwrap_or(0);
+    if run.splits().len() < 2 || pb == 0 {
+        return None;
+    }
+    let compare = if golds {
+        run.gold_times_for(method)[current]
+    } else {
+        pb
+    };
+    Some(split_time as i128 - compare as i128)
+}
+
+/// Get how much faster (negative) or slower (positive) a finished segment was than `comparison`, its time in the
+/// personal best or its best time.
+///
+/// The comparison has to be from before the split was finished, since finishing a run can replace it; the times sent
+/// with [`StateChange::EnterSplit`](crate::timer::state::StateChange::EnterSplit) are. Returns None if either time is
+/// not known, i.e. because the split was skipped.
+pub fn segment_diff(time: u128, comparison: u128) -> Option<i128> {
+    if time == 0 || comparison == 0 {
+        None
+    } else {
+        Some(time as i128 - comparison as i128)
+    }
+}
+
+/// Get the best time the current attempt can still finish with.
+///
+/// This is the time so far plus the best times of the segments that are left. The current segment counts as whichever
+/// is longer of its best time and the time already spent on it. `current` is the index of the split being run, `time`
+/// is the time of the attempt and `split_time` is the time spent on the current split. Before an attempt, with all of
+/// them 0, this is the sum of best segments.
+pub fn best_possible_time(
+    run: &Run,
+    method: TimingMethod,
+    current: usize,
+    time: u128,
+    split_time: u128,
+) -> u128 {
+    let golds = run.gold_times_for(method);
+    let current_gold = golds.get(current).copied().unwrap_or(0);
+    time.saturating_sub(split_time)
+        + split_time.max(current_gold)
+        + golds.iter().skip(current + 1).sum::<u128>()
+}
+
+/// Get how much time could be saved on the split at `idx`: the difference between its segment in the personal best
+/// and its best segment.
+///
+/// Returns None if either of them is not known.
+pub fn possible_time_save(run: &Run, method: TimingMethod, idx: usize) -> Option<u128> {
+    let pb = *run.pb_times_for(method).get(idx)?;
+    let gold = *run.gold_times_for(method).get(idx)?;
+    if pb == 0 || gold == 0 {
+        None
+    } else {
+        Some(pb.saturating_sub(gold))
+    }
+}
+
+/// Get how much time could be saved on the split at `from` and every split after it. Splits where the time save is
+/// not known are left out.
+pub fn total_possible_time_save(run: &Run, method: TimingMethod, from: usize) -> u128 {
+    (from..run.pb_times_for(method).len())
+        .filter_map(|idx| possible_time_save(run, method, idx))
+        .sum()
+}
+
+/// Get the segment times of a comparison, with one time for each split.
+///
+/// The median, balanced pb and latest run comparisons use the attempt history in the timing method, so in game time
+/// attempts recorded without game times are left out. Custom comparisons have one set of times used for both timing methods, and
+/// a custom comparison that the run doesn't have has no times.
+pub fn comparison_segments(run: &Run, method: TimingMethod, comparison: Comparison) -> Vec<u128> {
+    match comparison {
+        Comparison::PersonalBest => run.pb_times_for(method).clone(),
+        Comparison::Golds => run.gold_times_for(method).clone(),
+        Comparison::Average => run
+            .sum_times()
+            .iter()
+            .map(|&(n, t)| t.checked_div(n).unwrap_or(t))
+            .collect(),
+        Comparison::Median => segment_history(run, method)
+            .iter()
+            .map(|times| match times.len() {
+                0 => 0,
+                n if n % 2 == 1 => times[n / 2],
+                n => (times[n / 2 - 1] + times[n / 2]) / 2,
+            })
+            .collect(),
+        Comparison::BalancedPb => balanced_pb(run, method),
+        Comparison::LatestRun => {
+            let mut segments = vec![0; run.pb_times().len()];
+            // a reset attempt would only cover part of the run
+            let latest = run
+                .attempt_history()
+                .iter()
+                .rev()
+                .find(|a| a.end() == AttemptEnd::Finished);
+            if let Some(attempt) = latest {
+                for (segment, time) in segments.iter_mut().zip(attempt.segments_for(method)) {
+                    *segment = time.unwrap_or(0);
+                }
+            }
+            segments
+        }
+        Comparison::Custom(idx) => match run.custom_comparisons().get(idx) {
+            Some(custom) => custom.segments(),
+            None => vec![0; run.pb_times().len()],
+        },
+        Comparison::None => vec![0; run.pb_times().len()],
+    }
+}
+
+/// Get the times of a comparison that finishes at `goal`, as the time of the run at the end of each split like a
+/// [`CustomComparison`](crate::timer::CustomComparison) has.
+///
+/// The goal is shared between the segments in proportion to their times in `basis`, i.e. the best segments or the
+/// personal best, the way LiveSplit's goal tool does. Splits whose segment has no time in the basis get no time, so
+/// the next segment that has one covers both, except for the ones at the end, which finish at `goal` so the run always
+/// does. Returns None if the basis has no times at all.
+pub fn goal_times(
+    run: &Run,
+    method: TimingMethod,
+    goal: u128,
+    basis: Comparison,
+) -> Option<Vec<u128>> {
+    let segments = comparison_segments(run, method, basis);
+    let total: u128 = segments.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut sum = 0;
+    Some(
+        segments
+            .iter()
+            .map(|&segment| {
+                if segment == 0 {
+                    return if sum == total { goal } else { 0 };
+                }
+                sum += segment;
+                sum * goal / total
+            })
+            .collect(),
+    )
+}
+
+// every recorded time of each segment, sorted
+fn segment_history(run: &Run, method: TimingMethod) -> Vec<Vec<u128>> {
+    let mut history = vec![vec![]; run.pb_times().len()];
+    for attempt in run.attempt_history() {
+        for (times, time) in history.iter_mut().zip(attempt.segments_for(method)) {
+            times.extend(time);
+        }
+    }
+    for times in &mut history {
+        times.sort_unstable();
+    }
+    history
+}
+
+// the time `p` of the way from the fastest to the slowest of `sorted`
+fn percentile(sorted: &[u128], p: f64) -> Option<u128> {
+    let last = sorted.len().checked_sub(1)?;
+    let pos = p * last as f64;
+    let idx = pos as usize;
+    let next = sorted[(idx + 1).min(last)];
+    Some(sorted[idx] + ((next - sorted[idx]) as f64 * pos.fract()).round() as u128)
+}
+
+// the segments at the same percentile of their history, with that percentile chosen so they add up to the pb
+fn balanced_pb(run: &Run, method: TimingMethod) -> Vec<u128> {
+    let pb_segments = run.pb_times_for(method);
+    let pb: u128 = pb_segments.iter().sum();
+    let history = segment_history(run, method);
+    if pb == 0 || history.iter().all(Vec::is_empty) {
+        return pb_segments.clone();
+    }
+    // segments without any history keep their pb time
+    let at = |p: f64| -> Vec<u128> {
+        history
+            .iter()
+            .zip(pb_segments)
+            .map(|(times, &pb)| percentile(times, p).unwrap_or(pb))
+            .collect()
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+        let mid = (low + high) / 2.0;
+        if at(mid).iter().sum::<u128>() < pb {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    // the history may not be able to add up to the pb exactly, so scale the segments to make it
+    let mut segments = at(high);
+    let sum: u128 = segments.iter().sum();
+    if sum == 0 {
+        return pb_segments.clone();
+    }
+    for segment in &mut segments {
+        *segment = *segment * pb / sum;
+    }
+    let scaled: u128 = segments.iter().sum();
+    if let Some(last) = segments.iter_mut().rev().find(|s| **s != 0) {
+        *last += pb - scaled;
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::Attempt;
+
+    fn run() -> Run {
+        let mut run = Run::empty();
+        run.set_splits(&vec!["a".into(), "b".into(), "c".into()]);
+        run.set_pb_times(&vec![1000, 2000, 0]);
+        run.set_gold_times(&vec![900, 1500, 1200]);
+        run
+    }
+
+    #[test]
+    fn test_pace_and_diff() {
+        let run = run();
+        let method = TimingMethod::RealTime;
+        assert_eq!(sum_of_best(&run, method), 3600);
+        assert_eq!(sum_of_best(&run, TimingMethod::GameTime), 0);
+        // 1100ms into the run, on the second split
+        assert_eq!(pace(&run, method, 1, 1100, false), Some(1100));
+        assert_eq!(pace(&run, method, 1, 1100, true), Some(2300));
+        assert_eq!(pace(&run, method, 2, 3000, false), None);
+        assert_eq!(split_diff(&run, method, 1, 1800, false), Some(-200));
+        assert_eq!(split_diff(&run, method, 1, 1800, true), Some(300));
+        assert_eq!(split_diff(&run, method, 2, 1800, false), None);
+        assert_eq!(segment_diff(1100, 1000), Some(100));
+        assert_eq!(segment_diff(900, 1000), Some(-100));
+        assert_eq!(segment_diff(0, 1000), None);
+    }
+
+    #[test]
+    fn test_history_comparisons() {
+        use AttemptEnd::{Finished, Reset};
+        let mut run = run();
+        let method = TimingMethod::RealTime;
+        let attempt = |end: AttemptEnd, segments: &[Option<u128>]| {
+            Attempt::new(0, end, &segments.to_vec(), 0)
+        };
+        assert_eq!(
+            comparison_segments(&run, method, Comparison::Median),
+            [0, 0, 0]
+        );
+        assert_eq!(
+            comparison_segments(&run, method, Comparison::BalancedPb),
+            [1000, 2000, 0]
+        );
+        run.add_attempt(attempt(Finished, &[Some(1200), Some(2400), Some(1400)]));
+        run.add_attempt(attempt(Finished, &[Some(900), Some(1500), Some(1200)]));
+        run.add_attempt(attempt(Reset, &[Some(1000), Some(2000)]));
+        run.add_attempt(attempt(Reset, &[Some(1100), None]));
+        assert_eq!(
+            comparison_segments(&run, method, Comparison::Median),
+            [1050, 2000, 1300]
+        );
+        // the latest attempts were reset, so the latest run is the last one that finished
+        assert_eq!(
+            comparison_segments(&run, method, Comparison::LatestRun),
+            [900, 1500, 1200]
+        );
+        assert_eq!(
+            comparison_segments(&run, TimingMethod::GameTime, Comparison::LatestRun),
+            [0, 0, 0]
+        );
+        run.set_pb_times(&vec![1000, 2000, 1300]);
+        let balanced = comparison_segments(&run, method, Comparison::BalancedPb);
+        assert_eq!(balanced.iter().sum::<u128>(), 4300);
+        // the first segment of the pb was better compared to its history than the second, so it gets more time
+        assert!(balanced[0] > 1000 && balanced[1] < 2000);
+        // game times only come from attempts that have them
+        let mut timed = attempt(Finished, &[Some(1000), Some(2000), Some(1000)]);
+        timed.set_game_segments(&vec![Some(800), Some(1900), None]);
+        run.add_attempt(timed);
+        let game = TimingMethod::GameTime;
+        assert_eq!(
+            comparison_segments(&run, game, Comparison::Median),
+            [800, 1900, 0]
+        );
+        assert_eq!(
+            comparison_segments(&run, game, Comparison::LatestRun),
+            [800, 1900, 0]
+        );
+        run.set_game_pb_times(&vec![1000, 2000, 0]);
+        assert_eq!(
+            comparison_segments(&run, game, Comparison::BalancedPb),
+            [888, 2112, 0]
+        );
+    }
+
+    #[test]
+    fn test_goal_times() {
+        let run = run();
+        let method = TimingMethod::RealTime;
+        assert_eq!(
+            goal_times(&run, method, 3000, Comparison::Golds),
+            Some(vec![750, 2000, 3000])
+        );
+        // the last split has no pb time, so the goal is spread over the first two and the run still ends at it
+        assert_eq!(
+            goal_times(&run, method, 2700, Comparison::PersonalBest),
+            Some(vec![900, 2700, 2700])
+        );
+        assert_eq!(goal_times(&run, method, 3000, Comparison::None), None);
+    }
+
+    #[test]
+    fn test_best_possible_time() {
+        let run = run();
+        let method = TimingMethod::RealTime;
+        assert_eq!(best_possible_time(&run, method, 0, 0, 0), 3600);
+        // 500ms into the first split, which is still faster than its gold
+        assert_eq!(best_possible_time(&run, method, 0, 500, 500), 3600);
+        // 1000ms into the first split, 100ms slower than its gold
+        assert_eq!(best_possible_time(&run, method, 0, 1000, 1000), 3700);
+        // first split done in 1100ms, 200ms into the second
+        assert_eq!(best_possible_time(&run, method, 1, 1300, 200), 3800);
+    }
+
+    #[test]
+    fn test_possible_time_save() {
+        let run = run();
+        let method = TimingMethod::RealTime;
+        assert_eq!(possible_time_save(&run, method, 0), Some(100));
+        assert_eq!(possible_time_save(&run, method, 1), Some(500));
+        assert_eq!(possible_time_save(&run, method, 2), None);
+        assert_eq!(possible_time_save(&run, method, 3), None);
+        assert_eq!(total_possible_time_save(&run, method, 0), 600);
+        assert_eq!(total_possible_time_save(&run, method, 1), 500);
+        assert_eq!(total_possible_time_save(&run, method, 2), 0);
+    }
+}
@@ -0,0 +1,200 @@
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+/// A color with transparency.
+///
+/// In the config file a color is either a hex string (`"#rrggbb"` or `"#rrggbbaa"`) or a tuple of numbers from 0 to
+/// 255 (`(r, g, b)` or `(r, g, b, a)`). Colors are always written back as hex strings.
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 0 is fully transparent, 255 is opaque.
+    pub a: u8,
+}
+
+impl Color {
+    /// Create an opaque color.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+    /// Create a color with transparency.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Color::rgb(r, g, b)
+    }
+}
+
+impl From<Color> for (u8, u8, u8, u8) {
+    fn from(c: Color) -> Self {
+        (c.r, c.g, c.b, c.a)
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+    /// Parse a hex color, i.e. `#ff5a5a` or `#ff5a5a80`. The `#` is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("invalid hex color `{}`", s))
+        };
+        if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid hex color `{}`", s));
+        }
+        match hex.len() {
+            6 => Ok(Color::rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Ok(Color::rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => Err(format!("invalid hex color `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+        impl<'de> Visitor<'de> for ColorVisitor {
+            type Value = Color;
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a hex color string or a tuple of 3 or 4 numbers")
+            }
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Color, E> {
+                s.parse().map_err(E::custom)
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Color, A::Error> {
+                let mut channel = |i: usize| {
+                    seq.next_element::<u8>()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))
+                };
+                let (r, g, b) = (channel(0)?, channel(1)?, channel(2)?);
+                let a = seq.next_element()?.unwrap_or(255);
+                Ok(Color::rgba(r, g, b, a))
+            }
+        }
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+#[serde(default)]
+/// The colors mist is drawn with.
+///
+/// Colors that are left out of the config keep their default.
+pub struct ColorScheme {
+    /// Ahead of the comparison and gaining time.
+    pub ahead: Color,
+    /// Behind the comparison and losing time.
+    pub behind: Color,
+    /// Behind the comparison but gaining time.
+    pub gaining: Color,
+    /// Ahead of the comparison but losing time.
+    pub losing: Color,
+    /// A new best segment.
+    pub gold: Color,
+    /// The window background.
+    pub background: Color,
+    /// Split names and times, and the timer when there is nothing to compare against.
+    pub text: Color,
+    /// The box behind the current split.
+    pub highlight: Color,
+    /// The lines between splits.
+    pub separator: Color,
+    /// The names of panels.
+    pub panel_label: Color,
+    /// The times in panels.
+    pub panel_value: Color,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme {
+            ahead: Color::rgb(0, 255, 0),
+            behind: Color::rgb(255, 0, 0),
+            gaining: Color::rgb(255, 90, 90),
+            losing: Color::rgb(135, 255, 125),
+            gold: Color::rgb(255, 255, 0),
+            background: Color::rgb(0, 0, 0),
+            text: Color::rgb(255, 255, 255),
+            highlight: Color::rgb(0, 0, 255),
+            separator: Color::rgb(128, 128, 128),
+            panel_label: Color::rgb(255, 255, 255),
+            panel_value: Color::rgb(255, 255, 255),
+        }
+    }
+}
+
+/// Read the list of colors used by configs from before [`ColorScheme`]:
+/// ahead, behind, gaining, losing, gold and background, in that order.
+impl From<[(u8, u8, u8); 6]> for ColorScheme {
+    fn from(old: [(u8, u8, u8); 6]) -> Self {
+        ColorScheme {
+            ahead: old[0].into(),
+            behind: old[1].into(),
+            gaining: old[2].into(),
+            losing: old[3].into(),
+            gold: old[4].into(),
+            background: old[5].into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!("#ff5a5a".parse(), Ok(Color::rgb(255, 90, 90)));
+        assert_eq!("00ff0080".parse(), Ok(Color::rgba(0, 255, 0, 128)));
+        assert!("#ff5a5".parse::<Color>().is_err());
+        assert!("#gg0000".parse::<Color>().is_err());
+        assert!("#+f0000".parse::<Color>().is_err());
+        assert_eq!(Color::rgb(255, 90, 90).to_string(), "#ff5a5a");
+        assert_eq!(Color::rgba(0, 255, 0, 128).to_string(), "#00ff0080");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let scheme: ColorScheme =
+            ron::from_str("(ahead: \"#00ff00\", behind: (255, 0, 0, 128), text: (200, 200, 200))")
+                .unwrap();
+        assert_eq!(scheme.ahead, Color::rgb(0, 255, 0));
+        assert_eq!(scheme.behind, Color::rgba(255, 0, 0, 128));
+        assert_eq!(scheme.text, Color::rgb(200, 200, 200));
+        assert_eq!(scheme.gold, ColorScheme::default().gold);
+        let out = ron::to_string(&scheme).unwrap();
+        assert_eq!(ron::from_str::<ColorScheme>(&out).unwrap(), scheme);
+    }
+}
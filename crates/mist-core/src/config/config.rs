@@ -1,123 +1,221 @@
 // handle configuration of color and font path
-use super::Font;
+use super::fields::{self, parse_value};
+use super::paths;
 use super::KeybindsRaw;
-use super::LayoutOpts;
 use super::Panel;
-use ron::de::from_reader;
+use super::Theme;
+use crate::Error;
 use ron::extensions::Extensions;
 use ron::ser::{to_string_pretty, PrettyConfig};
 use serde::{Deserialize, Serialize};
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Debug)]
-#[cfg(feature = "bg")]
+#[serde(default)]
 /// Configuration of mist.
+///
+/// How mist looks is set by a [`Theme`]: either the theme file named by `theme`, or the config's own `style`.
 pub struct Config {
     def_file: Option<String>,
-    img_file: Option<String>,
-    img_scaled: bool,
-    colors: [(u8, u8, u8); 6],
     frame_rounding: Option<u128>,
-    layout: LayoutOpts,
     panels: Vec<Panel>,
-    t_font: Font,
-    s_font: Font,
-    font_size: (u16, u16),
-    binds: KeybindsRaw,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[cfg(not(feature = "bg"))]
-/// Configuration of mist.
-pub struct Config {
-    def_file: Option<String>,
-    colors: [(u8, u8, u8); 6],
-    frame_rounding: Option<u128>,
-    layout: LayoutOpts,
-    panels: Vec<Panel>,
-    t_font: Font,
-    s_font: Font,
-    font_size: (u16, u16),
     binds: KeybindsRaw,
+    server_port: Option<u16>,
+    theme: Option<String>,
+    style: Theme,
+    #[serde(skip)]
+    active_theme: Option<Theme>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    warnings: Vec<String>,
 }
 
 impl Config {
-    /// Attempts to open and parse mist's default config
+    /// The newest version of the config file format.
+    pub const VERSION: u8 = 1;
+    /// Attempts to open and parse mist's default config, as found by [`paths::config_file`].
+    ///
+    /// If the file doesn't exist yet, the config bundled with mist is read instead, or the default is returned if
+    /// there is none. Either way the Config is saved to the user's config.
+    ///
+    /// # Errors
+    ///
+    /// * If the file exists but cannot be read or parsed. See [`parse`](Config::parse).
+    pub fn open() -> Result<Self, Error> {
+        let path = paths::config_file();
+        if path.exists() {
+            return Self::open_path(path);
+        }
+        let mut cfg = Self::open_path(paths::default_config())?;
+        cfg.path = path;
+        Ok(cfg)
+    }
+    /// Like [`open`](Config::open), but with the config at `path` instead of the default one.
     ///
-    /// If a Config cannot be parsed, returns the default.
-    /// Only will return `Err` if it cannot read/write to the config file.
-    pub fn open() -> Result<Self, String> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open("assets/mist.cfg")
-            .map_err(|e| e.to_string())?;
-        let cfg: Self = from_reader(&file).unwrap_or_default();
+    /// The Config remembers `path` and is saved back to it. If the theme it names can't be loaded, its own style is
+    /// used and the problem is added to the [`warnings`](Config::warnings). Problems with single keys in the theme
+    /// are in the theme's own [`warnings`](Theme::warnings).
+    pub fn open_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut cfg = match std::fs::read_to_string(path) {
+            Ok(s) => Self::parse(&s)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e.into()),
+        };
+        cfg.path = path.to_owned();
+        cfg.load_theme();
         Ok(cfg)
     }
+    /// Read the config from the file it was opened from again, i.e. after it was edited.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read or parsed. See [`parse`](Config::parse).
+    pub fn reload(&self) -> Result<Self, Error> {
+        let mut cfg = Self::parse(&std::fs::read_to_string(&self.path)?)?;
+        cfg.path = self.path.clone();
+        cfg.load_theme();
+        Ok(cfg)
+    }
+    /// Parse a Config from the contents of a config file.
+    ///
+    /// Each key is read on its own, so keys that are missing or have a value that can't be parsed get their default
+    /// value without affecting the rest of the config. Invalid and unknown keys are listed in
+    /// [`warnings`](Config::warnings). Files without a version line are from before the format was versioned, and
+    /// are read the same way as version 1. Theme settings at the top level, as written before themes existed, are
+    /// read into the config's own style.
+    ///
+    /// The theme named by the config is not loaded; see [`set_theme`](Config::set_theme).
+    ///
+    /// # Errors
+    ///
+    /// * If the file is of a version newer than this version of mist-core knows about.
+    /// * If the file is not a list of keys and values in parentheses, i.e. a parenthesis or quote is missing.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let (version, start) = fields::version(s);
+        if version > Self::VERSION as u32 {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let mut cfg = Self::default();
+        for field in fields::fields(s, start)? {
+            let value = field.value;
+            let res = match field.key {
+                "def_file" => parse_value(&mut cfg.def_file, value),
+                "frame_rounding" => parse_value(&mut cfg.frame_rounding, value),
+                "panels" => parse_value(&mut cfg.panels, value),
+                "binds" => parse_value(&mut cfg.binds, value),
+                "server_port" => parse_value(&mut cfg.server_port, value),
+                "theme" => parse_value(&mut cfg.theme, value),
+                "style" => {
+                    let style = match fields::struct_fields(s, field.start) {
+                        Ok((style, _)) => style,
+                        Err(e) => {
+                            cfg.warnings.push(format!(
+                                "invalid value for `style`, using the default: {}",
+                                e
+                            ));
+                            continue;
+                        }
+                    };
+                    for field in style {
+                        match cfg.style.apply(&field) {
+                            Some(Ok(())) => {}
+                            Some(Err(e)) => cfg.warnings.push(field.invalid(e)),
+                            None => cfg.warnings.push(field.unknown()),
+                        }
+                    }
+                    Ok(())
+                }
+                _ => match cfg.style.apply(&field) {
+                    Some(res) => res,
+                    None => {
+                        cfg.warnings.push(field.unknown());
+                        continue;
+                    }
+                },
+            };
+            if let Err(e) = res {
+                cfg.warnings.push(field.invalid(e));
+            }
+        }
+        Ok(cfg)
+    }
+    // load the theme named in the file, falling back to the config's own style
+    fn load_theme(&mut self) {
+        if let Some(name) = self.theme.clone() {
+            match Theme::find(&name) {
+                Ok(theme) => self.active_theme = Some(theme),
+                Err(e) => self
+                    .warnings
+                    .push(format!("could not load theme {}: {}", name, e)),
+            }
+        }
+    }
+    /// Get the problems found when the Config was parsed, one message for each key that was ignored.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+    /// Get the path the Config is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Set the path the Config is saved to.
+    pub fn set_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.path = path.as_ref().to_owned();
+    }
     /// Get the split file from the Config. Returns None if no file set.
     pub fn file(&self) -> Option<&String> {
         self.def_file.as_ref()
     }
-    #[cfg(feature = "bg")]
-    /// Get the path to the image file to be used as a background for the timer.
-    pub fn img(&self) -> Option<&String> {
-        self.img_file.as_ref()
-    }
-    #[cfg(feature = "bg")]
-    /// Determine whether the image should be scaled to fit the screen or cropped.
-    pub fn img_scaled(&self) -> bool {
-        self.img_scaled
-    }
     /// Set the split file path to a new one.
     pub fn set_file(&mut self, file: &str) {
         self.def_file = Some(file.to_owned());
     }
-    /// Get the Font used for the display timer.
-    pub fn tfont(&self) -> &Font {
-        &self.t_font
-    }
-    /// Get the Font used for the rows of splits.
-    pub fn sfont(&self) -> &Font {
-        &self.s_font
+    /// Get the theme in use: the theme file named by the config if it was loaded, otherwise the config's own style.
+    pub fn theme(&self) -> &Theme {
+        self.active_theme.as_ref().unwrap_or(&self.style)
     }
-    /// Get the tuple of font sizes for the timer and split fonts respectively.
-    pub fn fsize(&self) -> (u16, u16) {
-        self.font_size
+    /// Get the name of the theme file the config uses, if any.
+    pub fn theme_name(&self) -> Option<&str> {
+        self.theme.as_deref()
     }
-    /// Get the list of colors to be used for the timer.
-    pub fn color_list(&self) -> [(u8, u8, u8); 6] {
-        self.colors
+    /// Switch to the theme file called `name`, or to the config's own style if `name` is None.
+    ///
+    /// # Errors
+    ///
+    /// * If the theme can't be found or loaded. The current theme is kept.
+    pub fn set_theme(&mut self, name: Option<&str>) -> Result<(), Error> {
+        self.active_theme = name.map(Theme::find).transpose()?;
+        self.theme = name.map(str::to_owned);
+        Ok(())
     }
     /// Write the config to the file.
     ///
     /// # Errors
     ///
     /// * If the serialization fails.
-    /// * If the file cannot be written to or opened.
-    pub fn save(&self) -> Result<(), String> {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .open("assets/mist.cfg")
-            .map_err(|e| e.to_string())?;
+    /// * If the file or the directory it goes in cannot be created or written to.
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = File::create(&self.path)?;
         let string = to_string_pretty(
             self,
             PrettyConfig::new().extensions(Extensions::IMPLICIT_SOME),
         )
-        .map_err(|e| e.to_string())?;
-        file.write(string.as_bytes()).map_err(|e| e.to_string())?;
+        .map_err(|e| Error::Serialize(e.to_string()))?;
+        file.write_all(format!("version {}\n", Self::VERSION).as_bytes())?;
+        file.write_all(string.as_bytes())?;
         Ok(())
     }
     /// Get the keybinds in string form as names of keys.
     pub fn binds(&self) -> &KeybindsRaw {
         &self.binds
     }
-    /// Get the layout options.
-    pub fn layout(&self) -> &LayoutOpts {
-        &self.layout
-    }
     /// Get the list of timing display panels.
     pub fn panels(&self) -> &Vec<Panel> {
         &self.panels
@@ -127,53 +225,139 @@ impl Config {
     pub fn rounding(&self) -> Option<u128> {
         self.frame_rounding
     }
+    /// Get the port to run the LiveSplit Server compatible server on.
+    /// None means the server is off.
+    pub fn server_port(&self) -> Option<u16> {
+        self.server_port
+    }
 }
 
-#[cfg(feature = "bg")]
 impl Default for Config {
     fn default() -> Config {
         Config {
             def_file: None,
-            img_file: None,
-            img_scaled: false,
-            colors: [
-                (0, 255, 0),
-                (255, 0, 0),
-                (255, 90, 90),
-                (135, 255, 125),
-                (255, 255, 0),
-                (0, 0, 0),
-            ],
             frame_rounding: Some(30),
-            layout: LayoutOpts::default(),
             panels: vec![],
-            t_font: Font::timer_default(),
-            s_font: Font::splits_default(),
-            font_size: (60, 25),
             binds: KeybindsRaw::default(),
+            server_port: None,
+            theme: None,
+            style: Theme::default(),
+            active_theme: None,
+            path: PathBuf::new(),
+            warnings: vec![],
         }
     }
 }
-#[cfg(not(feature = "bg"))]
-impl Default for Config {
-    fn default() -> Config {
-        Config {
-            def_file: None,
-            colors: [
-                (0, 255, 0),
-                (255, 0, 0),
-                (255, 90, 90),
-                (135, 255, 125),
-                (255, 255, 0),
-                (0, 0, 0),
-            ],
-            frame_rounding: Some(30),
-            layout: LayoutOpts::default(),
-            panels: vec![],
-            t_font: Font::timer_default(),
-            s_font: Font::splits_default(),
-            font_size: (60, 25),
-            binds: KeybindsRaw::default(),
-        }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Color, ColorScheme};
+
+    #[test]
+    fn test_round_trip() {
+        let mut cfg = Config::default();
+        cfg.set_file("splits/any%.msf");
+        cfg.server_port = Some(16834);
+        let out = format!(
+            "version {}\n{}",
+            Config::VERSION,
+            to_string_pretty(
+                &cfg,
+                PrettyConfig::new().extensions(Extensions::IMPLICIT_SOME)
+            )
+            .unwrap()
+        );
+        let parsed = Config::parse(&out).unwrap();
+        assert!(parsed.warnings().is_empty());
+        assert_eq!(parsed.file(), Some(&"splits/any%.msf".to_owned()));
+        assert_eq!(parsed.server_port(), Some(16834));
+    }
+
+    #[test]
+    fn test_partial() {
+        // unversioned, with a bad value, a missing key and a key from some other program
+        let cfg = Config::parse(
+            "#![enable(implicit_some)]
+(
+    def_file: \"a (weird), file.msf\", // comment with a )
+    img_file: r#\"bg \"1\".png\"#,
+    colors: ((0, 255, 0)),
+    font_size: (30, 20),
+    wallpaper: \"/*\",
+)",
+        )
+        .unwrap();
+        assert_eq!(cfg.file(), Some(&"a (weird), file.msf".to_owned()));
+        assert_eq!(cfg.theme().fsize(), (30, 20));
+        assert_eq!(cfg.theme().colors(), ColorScheme::default());
+        assert_eq!(cfg.rounding(), Config::default().rounding());
+        assert_eq!(cfg.warnings().len(), 2);
+        assert!(cfg.warnings()[0].starts_with("line 5, column"));
+        assert_eq!(cfg.warnings()[1], "line 7: unknown key `wallpaper`");
+        // colors from before ColorScheme
+        let cfg = Config::parse(
+            "(colors: ((1, 2, 3), (0, 0, 0), (0, 0, 0), (0, 0, 0), (0, 0, 0), (9, 9, 9)))",
+        )
+        .unwrap();
+        assert!(cfg.warnings().is_empty());
+        assert_eq!(cfg.theme().colors().ahead, Color::rgb(1, 2, 3));
+        assert_eq!(cfg.theme().colors().background, Color::rgb(9, 9, 9));
+        assert_eq!(cfg.theme().colors().text, ColorScheme::default().text);
+        // theme settings in the style
+        let cfg = Config::parse(
+            "(theme: \"light\", style: (colors: (ahead: \"#010203\"), sparkles: true))",
+        )
+        .unwrap();
+        assert_eq!(cfg.theme_name(), Some("light"));
+        assert_eq!(cfg.theme().colors().ahead, Color::rgb(1, 2, 3));
+        assert_eq!(cfg.warnings(), ["line 1: unknown key `sparkles`"]);
+    }
+
+    #[test]
+    fn test_plain_serde() {
+        let s = "(
+    def_file: \"a.msf\",
+    frame_rounding: \"thirty\",
+    server_port: 16834,
+    font_size: (30, 20),
+    wallpaper: \"x\",
+)";
+        // deserializing the whole struct loses every key to one bad value, and can't say which keys were ignored
+        assert!(ron::de::from_str::<Config>(s).is_err());
+        let cfg = Config::parse(s).unwrap();
+        assert_eq!(cfg.file(), Some(&"a.msf".to_owned()));
+        assert_eq!(cfg.server_port(), Some(16834));
+        assert_eq!(cfg.rounding(), Config::default().rounding());
+        assert_eq!(cfg.warnings().len(), 2);
+        assert!(
+            cfg.warnings()[0].starts_with("line 3, column 21: invalid value for `frame_rounding`")
+        );
+        assert_eq!(cfg.warnings()[1], "line 6: unknown key `wallpaper`");
+        // theme keys at the top level, from before themes, would be skipped like unknown keys
+        let old = "(font_size: (30, 20))";
+        let plain = ron::de::from_str::<Config>(old).unwrap();
+        assert_eq!(plain.theme().fsize(), Theme::default().fsize());
+        assert_eq!(Config::parse(old).unwrap().theme().fsize(), (30, 20));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Config::parse("version 255\n()"),
+            Err(Error::UnsupportedVersion(255))
+        ));
+        assert!(matches!(
+            Config::parse("version 1\n(\n    def_file: \"oops,\n)"),
+            Err(Error::Parse {
+                line: 3,
+                col: 15,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Config::parse("(font_size: (1, 2)"),
+            Err(Error::Parse { .. })
+        ));
     }
 }
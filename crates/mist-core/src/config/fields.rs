@@ -0,0 +1,285 @@
+//! Read config and theme files one key at a time.
+//!
+//! The top level struct of the file is split into keys and values as text, and each value is then parsed on its own,
+//! so that one bad value only loses that key. Deserializing the whole struct with serde can't do this: one bad value
+//! fails the whole file, and unknown keys (including theme keys written at the top level by older versions) are
+//! skipped without a way to warn about them.
+use crate::Error;
+use ron::de::from_str;
+use serde::de::DeserializeOwned;
+
+/// A key and its value, as text.
+pub(super) struct Field<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    /// Where the value starts in the file, in bytes.
+    pub start: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Field<'_> {
+    /// Describe a key that isn't known.
+    pub fn unknown(&self) -> String {
+        format!("line {}: unknown key `{}`", self.line, self.key)
+    }
+    /// Describe a value that couldn't be parsed by [`parse_value`].
+    pub fn invalid(&self, e: ron::Error) -> String {
+        // the value is parsed after a line enabling implicit_some, so positions are relative to that line
+        let line = self.line + e.position.line.saturating_sub(2);
+        let col = if e.position.line <= 2 {
+            self.col + e.position.col.saturating_sub(1)
+        } else {
+            e.position.col
+        };
+        format!(
+            "line {}, column {}: invalid value for `{}`, using the default: {}",
+            line, col, self.key, e.code
+        )
+    }
+}
+
+/// Split a `version N` line off the start of a file, returning the version (0 if there is none) and where the rest
+/// of the file starts.
+pub(super) fn version(s: &str) -> (u32, usize) {
+    match s.split_once('\n') {
+        Some((first, _)) if first.starts_with("version ") => {
+            match first["version ".len()..].trim().parse::<u32>() {
+                Ok(v) => (v, first.len() + 1),
+                Err(_) => (0, 0),
+            }
+        }
+        _ => (0, 0),
+    }
+}
+
+/// Parse a value into `slot`, leaving it alone if the value is invalid.
+pub(super) fn parse_value<T: DeserializeOwned>(
+    slot: &mut T,
+    value: &str,
+) -> Result<(), ron::Error> {
+    *slot = from_str(&format!("#![enable(implicit_some)]\n{}", value))?;
+    Ok(())
+}
+
+/// Split the struct that makes up the file `s`, starting at byte `start`, into its keys and values.
+pub(super) fn fields(s: &str, start: usize) -> Result<Vec<Field<'_>>, Error> {
+    let b = s.as_bytes();
+    let mut i = start;
+    loop {
+        skip_blank(b, &mut i);
+        if !b[i..].starts_with(b"#!") {
+            break;
+        }
+        match b[i..].iter().position(|&c| c == b']') {
+            Some(end) => i += end + 1,
+            None => return Err(error(s, i, "unclosed attribute")),
+        }
+    }
+    let (fields, mut i) = struct_fields(s, i)?;
+    skip_blank(b, &mut i);
+    if i < b.len() {
+        return Err(error(s, i, "unexpected text after the end"));
+    }
+    Ok(fields)
+}
+
+/// Split a struct nested in `s` at byte `start`, i.e. the value of a [`Field`], into its keys and values.
+///
+/// Also returns where the struct ends.
+pub(super) fn struct_fields(s: &str, start: usize) -> Result<(Vec<Field<'_>>, usize), Error> {
+    let b = s.as_bytes();
+    let mut i = start;
+    // the struct name is optional
+    i += ident_len(&b[i..]);
+    skip_blank(b, &mut i);
+    if b.get(i) != Some(&b'(') {
+        return Err(error(s, i, "expected `(`"));
+    }
+    i += 1;
+    let mut fields = vec![];
+    loop {
+        skip_blank(b, &mut i);
+        match b.get(i) {
+            Some(b')') => {
+                i += 1;
+                break;
+            }
+            None => return Err(error(s, i, "expected `)`")),
+            _ => {}
+        }
+        let key_start = i;
+        i += ident_len(&b[i..]);
+        if i == key_start {
+            return Err(error(s, i, "expected a key"));
+        }
+        let key = &s[key_start..i];
+        skip_blank(b, &mut i);
+        if b.get(i) != Some(&b':') {
+            return Err(error(s, i, "expected `:`"));
+        }
+        i += 1;
+        skip_blank(b, &mut i);
+        let value_start = i;
+        skip_value(b, &mut i).map_err(|(i, msg)| error(s, i, msg))?;
+        let value = s[value_start..i].trim_end();
+        if value.is_empty() {
+            return Err(error(s, i, "expected a value"));
+        }
+        let (line, col) = position(s, value_start);
+        fields.push(Field {
+            key,
+            value,
+            start: value_start,
+            line,
+            col,
+        });
+        if b.get(i) == Some(&b',') {
+            i += 1;
+        }
+    }
+    Ok((fields, i))
+}
+
+fn error(s: &str, i: usize, msg: &str) -> Error {
+    let (line, col) = position(s, i);
+    Error::Parse {
+        line,
+        col,
+        msg: msg.to_owned(),
+    }
+}
+
+fn position(s: &str, i: usize) -> (usize, usize) {
+    let before = &s[..i];
+    let line = before.matches('\n').count() + 1;
+    let col = before[before.rfind('\n').map_or(0, |n| n + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, col)
+}
+
+fn ident_len(b: &[u8]) -> usize {
+    b.iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+        .count()
+}
+
+// skip whitespace and comments
+fn skip_blank(b: &[u8], i: &mut usize) {
+    loop {
+        while matches!(b.get(*i), Some(c) if c.is_ascii_whitespace()) {
+            *i += 1;
+        }
+        if b[*i..].starts_with(b"//") {
+            *i += b[*i..]
+                .iter()
+                .position(|&c| c == b'\n')
+                .unwrap_or(b.len() - *i);
+        } else if b[*i..].starts_with(b"/*") {
+            *i += b[*i + 2..]
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map_or(b.len() - *i, |end| end + 4);
+        } else {
+            return;
+        }
+    }
+}
+
+// move `i` to the `,` or `)` that ends the value starting at `i`, stepping over anything nested, quoted or commented
+fn skip_value(b: &[u8], i: &mut usize) -> Result<(), (usize, &'static str)> {
+    let mut depth = 0usize;
+    loop {
+        let c = match b.get(*i) {
+            Some(&c) => c,
+            None => return Err((*i, "unexpected end of file")),
+        };
+        match c {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth > 0 => depth -= 1,
+            b')' if depth == 0 => return Ok(()),
+            b']' | b'}' => return Err((*i, "unmatched bracket")),
+            b',' if depth == 0 => return Ok(()),
+            b'"' | b'\'' => {
+                let start = *i;
+                *i += 1;
+                while b.get(*i) != Some(&c) {
+                    match b.get(*i) {
+                        Some(b'\\') => *i += 2,
+                        Some(_) => *i += 1,
+                        None => return Err((start, "unclosed quote")),
+                    }
+                }
+            }
+            // raw strings, i.e. r#"..."#
+            b'r' if (*i == 0 || ident_len(&b[*i - 1..*i]) == 0)
+                && matches!(b.get(*i + 1), Some(b'"') | Some(b'#')) =>
+            {
+                let start = *i;
+                let hashes = b[*i + 1..].iter().take_while(|&&c| c == b'#').count();
+                *i += 1 + hashes;
+                if b.get(*i) != Some(&b'"') {
+                    continue;
+                }
+                let mut end = vec![b'"'];
+                end.resize(hashes + 1, b'#');
+                match b[*i + 1..].windows(end.len()).position(|w| w == end) {
+                    Some(n) => *i += n + end.len(),
+                    None => return Err((start, "unclosed quote")),
+                }
+            }
+            b'/' if matches!(b.get(*i + 1), Some(b'/') | Some(b'*')) => {
+                skip_blank(b, i);
+                continue;
+            }
+            _ => {}
+        }
+        *i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let s = "version 1\n#![enable(implicit_some)]\n(\n    a: (1, \"),\" /* ) */),\n    b: r#\"a\")\"#, // )\n    c: [1, (2)]\n)";
+        let (version, start) = version(s);
+        assert_eq!(version, 1);
+        let fields = fields(s, start).unwrap();
+        let found: Vec<_> = fields
+            .iter()
+            .map(|f| (f.key, f.value, f.line, f.col))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("a", "(1, \"),\" /* ) */)", 4, 8),
+                ("b", "r#\"a\")\"#", 5, 8),
+                ("c", "[1, (2)]", 6, 8),
+            ]
+        );
+        assert!(matches!(
+            fields_err("(a: [1)"),
+            Error::Parse { line: 1, .. }
+        ));
+        assert!(matches!(
+            fields_err("(a: \"1)"),
+            Error::Parse {
+                line: 1,
+                col: 5,
+                ..
+            }
+        ));
+    }
+
+    fn fields_err(s: &str) -> Error {
+        match fields(s, 0) {
+            Err(e) => e,
+            Ok(_) => panic!("{} should not split", s),
+        }
+    }
+}
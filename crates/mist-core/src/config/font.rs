@@ -1,3 +1,5 @@
+use super::paths;
+use crate::Error;
 use rust_fontconfig::{FcFontCache, FcPattern};
 use serde::{Deserialize, Serialize};
 
@@ -11,13 +13,17 @@ pub struct Font {
 }
 
 impl Font {
-    /// Get the contents of the font file requested and write them to `buf`.
+    /// Get the path to the font file requested.
+    ///
+    /// Font files are looked for with [`paths::resolve`], so the default fonts are found wherever the assets are.
     ///
     /// # Errors
-    /// * If the font cannot be opened, found or read.
-    pub fn get_path(&self) -> Result<String, String> {
+    /// * If the font is a system font that cannot be found.
+    pub fn get_path(&self) -> Result<String, Error> {
         if !self.system {
-            Ok(self.path_name.clone())
+            Ok(paths::resolve(&self.path_name)
+                .to_string_lossy()
+                .into_owned())
         } else {
             let cache = FcFontCache::build();
             let pat = FcPattern {
@@ -28,7 +34,7 @@ impl Font {
             if let Some(font) = res {
                 Ok(font.path.clone())
             } else {
-                Err("Could not find system font".to_owned())
+                Err(Error::FontNotFound(self.path_name.clone()))
             }
         }
     }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 /// The raw representation of mist keybinds as strings.
 pub struct KeybindsRaw {
     pub pause: String,
@@ -11,6 +12,8 @@ pub struct KeybindsRaw {
     pub next_comp: String,
     pub load_splits: String,
     pub load_config: String,
+    pub switch_timing: String,
+    pub next_theme: String,
 }
 
 impl Default for KeybindsRaw {
@@ -25,6 +28,8 @@ impl Default for KeybindsRaw {
             next_comp: "Right".to_owned(),
             load_splits: "F1".to_owned(),
             load_config: "F2".to_owned(),
+            switch_timing: "T".to_owned(),
+            next_theme: "F3".to_owned(),
         }
     }
 }
@@ -1,6 +1,8 @@
+use super::Panel;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 /// Options for mist's visual layout.
 pub struct LayoutOpts {
     /// Whether to put the split names on the same line as their times.
@@ -9,6 +11,63 @@ pub struct LayoutOpts {
     pub panels_top: bool,
     /// Whether to place the large display timer at the top of the window.
     pub timer_top: bool,
+    /// Whether to show the game, category and attempt count at the top of the window.
+    pub title: bool,
+    /// Whether to show the time spent on the current split under the timer.
+    pub segment_timer: bool,
+    /// The parts of the window from top to bottom.
+    ///
+    /// If this is empty, the layout is made from the options above. See [`components`](LayoutOpts::components).
+    pub components: Vec<Component>,
+}
+
+impl LayoutOpts {
+    /// Get the parts of the window from top to bottom.
+    ///
+    /// If no components are set, a layout is built the way mist has always been laid out: the splits, the timer and
+    /// then `panels`, with the timer and panels moved to the top by `timer_top` and `panels_top`, a title above
+    /// everything if `title` is set and a segment timer under the timer if `segment_timer` is set. Panels are stacked
+    /// upwards in the order they are listed.
+    pub fn components(&self, panels: &[Panel]) -> Vec<Component> {
+        if !self.components.is_empty() {
+            return self.components.clone();
+        }
+        let mut timer = vec![Component::Timer {
+            font_size: None,
+            align: Align::Right,
+        }];
+        if self.segment_timer {
+            timer.push(Component::SegmentTimer {
+                font_size: None,
+                align: Align::Right,
+                pb: true,
+                best: true,
+            });
+        }
+        let panels = panels.iter().rev().map(|&p| Component::Panel(p));
+        let mut components = vec![];
+        if self.title {
+            components.push(Component::Title {
+                attempts: true,
+                font_size: None,
+                align: Align::Center,
+            });
+        }
+        if self.timer_top {
+            components.extend(timer.clone());
+        }
+        if self.panels_top {
+            components.extend(panels.clone());
+        }
+        components.push(Component::Splits { visible: None });
+        if !self.timer_top {
+            components.extend(timer);
+        }
+        if !self.panels_top {
+            components.extend(panels);
+        }
+        components
+    }
 }
 
 impl Default for LayoutOpts {
@@ -17,6 +76,144 @@ impl Default for LayoutOpts {
             inline_splits: true,
             panels_top: false,
             timer_top: false,
+            title: false,
+            segment_timer: false,
+            components: vec![],
         }
     }
 }
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+/// A part of the window. Each component is a row as wide as the window, and they are drawn from top to bottom.
+pub enum Component {
+    /// The game and category of the run, with the number of finished runs and attempts on the right if `attempts` is
+    /// true. `font_size` replaces the theme's split font size.
+    Title {
+        #[serde(default = "default_attempts")]
+        attempts: bool,
+        #[serde(default)]
+        font_size: Option<u16>,
+        #[serde(default = "default_title_align")]
+        align: Align,
+    },
+    /// The list of splits.
+    ///
+    /// `visible` is the number of splits to show. If it is None, the list fills whatever space the other components
+    /// leave.
+    Splits {
+        #[serde(default)]
+        visible: Option<usize>,
+    },
+    /// The large display timer. `font_size` replaces the theme's timer font size.
+    Timer {
+        #[serde(default)]
+        font_size: Option<u16>,
+        #[serde(default)]
+        align: Align,
+    },
+    /// The time spent on the current split, drawn like the timer. `font_size` replaces half the theme's timer font
+    /// size. `pb` and `best` show the split's time in the personal best and its best time on the other side.
+    SegmentTimer {
+        #[serde(default)]
+        font_size: Option<u16>,
+        #[serde(default)]
+        align: Align,
+        #[serde(default)]
+        pb: bool,
+        #[serde(default)]
+        best: bool,
+    },
+    /// An information panel.
+    Panel(Panel),
+    /// A line across the window.
+    Separator,
+    /// Empty space of the given height in pixels.
+    Spacer(u32),
+}
+
+fn default_attempts() -> bool {
+    true
+}
+
+fn default_title_align() -> Align {
+    Align::Center
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone, Default)]
+/// Where a component is placed across the window.
+pub enum Align {
+    Left,
+    Center,
+    #[default]
+    Right,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_components() {
+        let panels = [Panel::SumOfBest, Panel::Attempts];
+        let mut opts = LayoutOpts::default();
+        assert_eq!(
+            opts.components(&panels),
+            [
+                Component::Splits { visible: None },
+                Component::Timer {
+                    font_size: None,
+                    align: Align::Right
+                },
+                Component::Panel(Panel::Attempts),
+                Component::Panel(Panel::SumOfBest),
+            ]
+        );
+        opts.timer_top = true;
+        opts.title = true;
+        opts.segment_timer = true;
+        assert_eq!(
+            opts.components(&[]),
+            [
+                Component::Title {
+                    attempts: true,
+                    font_size: None,
+                    align: Align::Center
+                },
+                Component::Timer {
+                    font_size: None,
+                    align: Align::Right
+                },
+                Component::SegmentTimer {
+                    font_size: None,
+                    align: Align::Right,
+                    pb: true,
+                    best: true
+                },
+                Component::Splits { visible: None },
+            ]
+        );
+        opts.components = ron::from_str(
+            "#![enable(implicit_some)]
+[Title(), Timer(align: Center), Separator, Splits(visible: 8), Spacer(10), Panel(Pace(golds: true))]",
+        )
+        .unwrap();
+        assert_eq!(
+            opts.components(&panels),
+            [
+                Component::Title {
+                    attempts: true,
+                    font_size: None,
+                    align: Align::Center
+                },
+                Component::Timer {
+                    font_size: None,
+                    align: Align::Center
+                },
+                Component::Separator,
+                Component::Splits { visible: Some(8) },
+                Component::Spacer(10),
+                Component::Panel(Panel::Pace { golds: true }),
+            ]
+        );
+    }
+}
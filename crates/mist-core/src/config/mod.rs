@@ -1,7 +1,19 @@
 //! Structs related to configuration of mist
+mod colors;
 mod config;
+mod fields;
 mod font;
 mod keybinds;
 mod layout;
 mod panels;
-pub use {config::Config, font::Font, keybinds::KeybindsRaw, layout::LayoutOpts, panels::Panel};
+pub mod paths;
+mod theme;
+pub use {
+    colors::{Color, ColorScheme},
+    config::Config,
+    font::Font,
+    keybinds::KeybindsRaw,
+    layout::{Align, Component, LayoutOpts},
+    panels::Panel,
+    theme::Theme,
+};
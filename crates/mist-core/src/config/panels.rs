@@ -6,6 +6,22 @@ use serde::{Deserialize, Serialize};
 /// `golds` field represents whether to compare against gold times rather than pb times.
 pub enum Panel {
     SumOfBest,
-    CurrentSplitDiff { golds: bool },
-    Pace { golds: bool },
+    CurrentSplitDiff {
+        golds: bool,
+    },
+    Pace {
+        golds: bool,
+    },
+    Attempts,
+    /// Time saved or lost on the last split that was finished.
+    PreviousSegment {
+        golds: bool,
+    },
+    /// The fastest time the run can still finish with, given the time so far and the best segments left.
+    BestPossibleTime,
+    /// Time that could be saved by getting the best segment on the current split, or on it and every split after it if
+    /// `total` is true.
+    PossibleTimeSave {
+        total: bool,
+    },
 }
@@ -0,0 +1,227 @@
+//! Find where mist's config, assets and split files live.
+//!
+//! On Linux and other unixes (but not macOS) mist follows the XDG base directory spec: the config is read from
+//! `$XDG_CONFIG_HOME/mist/mist.cfg` (`~/.config/mist/mist.cfg`) and assets and split files from `$XDG_DATA_HOME/mist`
+//! (`~/.local/share/mist`) and then `mist` in each of `$XDG_DATA_DIRS`. Everywhere, the `assets` directory bundled
+//! with mist is used when nothing is found in those places. The bundled config is only read for its defaults and is
+//! never saved to, unless the platform has no user config directory.
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that overrides which config file is used.
+pub const CONFIG_ENV: &str = "MIST_CONFIG";
+const CONFIG_NAME: &str = "mist.cfg";
+
+/// Get the directory of assets that came with mist.
+///
+/// This is the `assets` directory next to the mist executable if there is one, otherwise `assets` in the working directory.
+pub fn bundled_dir() -> PathBuf {
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.join("assets")))
+    {
+        if dir.is_dir() {
+            return dir;
+        }
+    }
+    PathBuf::from("assets")
+}
+
+/// Get mist's directory in the user's config directory, if the platform has one.
+pub fn config_dir() -> Option<PathBuf> {
+    config_dir_with(&|var| env::var_os(var))
+}
+
+/// Get mist's directory in the user's data directory, if the platform has one.
+///
+/// It is searched before the system data directories and the bundled assets for fonts, themes and plugins.
+pub fn data_dir() -> Option<PathBuf> {
+    data_dir_with(&|var| env::var_os(var))
+}
+
+/// Find the config file to use, which is also where it is saved.
+///
+/// This is the file named by the `MIST_CONFIG` environment variable, or else the config in the user's config
+/// directory, whether or not it exists yet. Only on platforms without a user config directory is it the config in the
+/// bundled assets.
+pub fn config_file() -> PathBuf {
+    config_file_with(&|var| env::var_os(var), &bundled_dir())
+}
+
+/// Get the config bundled with mist, which has the defaults for a config that hasn't been saved yet.
+pub fn default_config() -> PathBuf {
+    bundled_dir().join(CONFIG_NAME)
+}
+
+/// Find an asset, such as a font or image, by its file name.
+///
+/// The user's data directory is searched first, then the system data directories and then the bundled assets.
+pub fn asset<P: AsRef<Path>>(name: P) -> PathBuf {
+    let name = name.as_ref();
+    search_dirs(&|var| env::var_os(var), &bundled_dir())
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| bundled_dir().join(name))
+}
+
+/// Get the directories theme files are looked for in, in order.
+///
+/// These are `themes` in each of the places searched by [`asset`].
+pub fn theme_dirs() -> Vec<PathBuf> {
+    search_dirs(&|var| env::var_os(var), &bundled_dir())
+        .into_iter()
+        .map(|dir| dir.join("themes"))
+        .collect()
+}
+
+/// Get the directories plugins are started from, in order.
+///
+/// These are `plugins` in each of the places searched by [`asset`].
+pub fn plugin_dirs() -> Vec<PathBuf> {
+    search_dirs(&|var| env::var_os(var), &bundled_dir())
+        .into_iter()
+        .map(|dir| dir.join("plugins"))
+        .collect()
+}
+
+/// Find a file named in a config or split file.
+///
+/// Absolute paths and paths that exist relative to the working directory are used as they are. Other paths are looked
+/// for in the same places as [`asset`], so that a split file named `splits/any%.msf` is found in the data directory and
+/// a font named `assets/DejaVuSans.ttf` (as written by older versions of mist) is found wherever the assets are.
+pub fn resolve<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() || path.exists() {
+        return path.to_owned();
+    }
+    let rel = path.strip_prefix("assets").unwrap_or(path);
+    let found = asset(rel);
+    if found.exists() {
+        found
+    } else {
+        path.to_owned()
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn xdg_dir(env: &dyn Fn(&str) -> Option<OsString>, var: &str, default: &str) -> Option<PathBuf> {
+    // relative paths are invalid according to the spec and should be ignored
+    match env(var).map(PathBuf::from) {
+        Some(p) if p.is_absolute() => Some(p),
+        _ => env("HOME").map(|home| PathBuf::from(home).join(default)),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn config_dir_with(env: &dyn Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    xdg_dir(env, "XDG_CONFIG_HOME", ".config").map(|p| p.join("mist"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn data_dir_with(env: &dyn Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    xdg_dir(env, "XDG_DATA_HOME", ".local/share").map(|p| p.join("mist"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_data_dirs(env: &dyn Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let dirs = env("XDG_DATA_DIRS")
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    env::split_paths(&dirs)
+        .filter(|p| p.is_absolute())
+        .map(|p| p.join("mist"))
+        .collect()
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn config_dir_with(_env: &dyn Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn data_dir_with(_env: &dyn Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn system_data_dirs(_env: &dyn Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    vec![]
+}
+
+fn search_dirs(env: &dyn Fn(&str) -> Option<OsString>, bundled: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = data_dir_with(env).into_iter().collect();
+    dirs.append(&mut system_data_dirs(env));
+    dirs.push(bundled.to_owned());
+    dirs
+}
+
+fn config_file_with(env: &dyn Fn(&str) -> Option<OsString>, bundled: &Path) -> PathBuf {
+    if let Some(p) = env(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(p);
+    }
+    config_dir_with(env)
+        .map(|d| d.join(CONFIG_NAME))
+        .unwrap_or_else(|| bundled.join(CONFIG_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mist-paths-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_config_file() {
+        let root = temp_dir("config");
+        let bundled = root.join("assets");
+        std::fs::create_dir_all(&bundled).unwrap();
+        std::fs::write(bundled.join(CONFIG_NAME), "").unwrap();
+        let config_home = root.join("config");
+        let env = |var: &str| match var {
+            "XDG_CONFIG_HOME" => Some(config_home.clone().into()),
+            _ => None,
+        };
+        // the user's config is used even before it exists, so the bundled one is never saved over
+        #[cfg(all(unix, not(target_os = "macos")))]
+        assert_eq!(
+            config_file_with(&env, &bundled),
+            config_home.join("mist").join(CONFIG_NAME)
+        );
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        assert_eq!(config_file_with(&env, &bundled), bundled.join(CONFIG_NAME));
+        let env = |var: &str| match var {
+            CONFIG_ENV => Some("/somewhere/else.cfg".into()),
+            _ => None,
+        };
+        assert_eq!(
+            config_file_with(&env, &bundled),
+            PathBuf::from("/somewhere/else.cfg")
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_search_dirs() {
+        let env = |var: &str| match var {
+            "HOME" => Some("/home/runner".into()),
+            "XDG_DATA_DIRS" => Some("/opt/share:relative".into()),
+            _ => None,
+        };
+        assert_eq!(
+            search_dirs(&env, Path::new("assets")),
+            vec![
+                PathBuf::from("/home/runner/.local/share/mist"),
+                PathBuf::from("/opt/share/mist"),
+                PathBuf::from("assets")
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use super::fields::{self, parse_value, Field};
+use super::paths;
+use super::{ColorScheme, Font, LayoutOpts};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+/// The look of mist: colors, fonts, background image and layout.
+///
+/// A theme is either part of the [`Config`](super::Config) or a theme file that the config refers to by name.
+/// Theme files are named `<name>.theme` and live in a `themes` directory in any of the places assets are found
+/// (see [`paths::theme_dirs`]).
+pub struct Theme {
+    colors: ColorScheme,
+    t_font: Font,
+    s_font: Font,
+    font_size: (u16, u16),
+    img_file: Option<String>,
+    img_scaled: bool,
+    layout: LayoutOpts,
+    #[serde(skip)]
+    warnings: Vec<String>,
+}
+
+impl Theme {
+    /// The newest version of the theme file format.
+    pub const VERSION: u8 = 1;
+    /// The extension of theme files.
+    pub const EXTENSION: &'static str = "theme";
+    /// Open the theme file called `name`.
+    ///
+    /// # Errors
+    ///
+    /// * If there is no theme called `name`.
+    /// * If the file cannot be read or parsed. See [`parse`](Theme::parse).
+    pub fn find(name: &str) -> Result<Self, Error> {
+        let file = format!("{}.{}", name, Self::EXTENSION);
+        for dir in paths::theme_dirs() {
+            match std::fs::read_to_string(dir.join(&file)) {
+                Ok(s) => return Self::parse(&s),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(Error::ThemeNotFound(name.to_owned()))
+    }
+    /// Get the names of every theme file that can be found, sorted and without duplicates.
+    pub fn list() -> Vec<String> {
+        let mut names: Vec<String> = paths::theme_dirs()
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != Self::EXTENSION {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+    /// Parse a Theme from the contents of a theme file.
+    ///
+    /// Like [`Config::parse`](super::Config::parse), each key is read on its own and problems with single keys are
+    /// listed in [`warnings`](Theme::warnings).
+    ///
+    /// # Errors
+    ///
+    /// * If the file is of a version newer than this version of mist-core knows about.
+    /// * If the file is not a list of keys and values in parentheses.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let (version, start) = fields::version(s);
+        if version > Self::VERSION as u32 {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let mut theme = Self::default();
+        for field in fields::fields(s, start)? {
+            match theme.apply(&field) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => theme.warnings.push(field.invalid(e)),
+                None => theme.warnings.push(field.unknown()),
+            }
+        }
+        Ok(theme)
+    }
+    // set the setting named by the field, or return None if there is no such setting
+    pub(super) fn apply(&mut self, field: &Field) -> Option<Result<(), ron::Error>> {
+        let value = field.value;
+        Some(match field.key {
+            // configs from before ColorScheme have a list of colors instead
+            "colors" => parse_value(&mut self.colors, value).or_else(|e| {
+                let mut old = [(0, 0, 0); 6];
+                parse_value(&mut old, value).map_err(|_| e)?;
+                self.colors = old.into();
+                Ok(())
+            }),
+            "t_font" => parse_value(&mut self.t_font, value),
+            "s_font" => parse_value(&mut self.s_font, value),
+            "font_size" => parse_value(&mut self.font_size, value),
+            "img_file" => parse_value(&mut self.img_file, value),
+            "img_scaled" => parse_value(&mut self.img_scaled, value),
+            "layout" => parse_value(&mut self.layout, value),
+            _ => return None,
+        })
+    }
+    /// Get the problems found when the Theme was parsed, one message for each key that was ignored.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+    /// Get the colors to draw the timer with.
+    pub fn colors(&self) -> ColorScheme {
+        self.colors
+    }
+    /// Get the Font used for the display timer.
+    pub fn tfont(&self) -> &Font {
+        &self.t_font
+    }
+    /// Get the Font used for the rows of splits.
+    pub fn sfont(&self) -> &Font {
+        &self.s_font
+    }
+    /// Get the tuple of font sizes for the timer and split fonts respectively.
+    pub fn fsize(&self) -> (u16, u16) {
+        self.font_size
+    }
+    #[cfg(feature = "bg")]
+    /// Get the path to the image file to be used as a background for the timer.
+    pub fn img(&self) -> Option<&String> {
+        self.img_file.as_ref()
+    }
+    #[cfg(feature = "bg")]
+    /// Determine whether the image should be scaled to fit the screen or cropped.
+    pub fn img_scaled(&self) -> bool {
+        self.img_scaled
+    }
+    /// Get the layout options.
+    pub fn layout(&self) -> &LayoutOpts {
+        &self.layout
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            colors: ColorScheme::default(),
+            t_font: Font::timer_default(),
+            s_font: Font::splits_default(),
+            font_size: (60, 25),
+            img_file: None,
+            img_scaled: false,
+            layout: LayoutOpts::default(),
+            warnings: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Color;
+
+    #[test]
+    fn test_parse() {
+        let theme = Theme::parse(
+            "version 1
+(
+    colors: (background: \"#fdf6e3\", text: \"#073642\"),
+    font_size: (50, 20),
+    layout: (inline_splits: false),
+    ringtone: \"beep\",
+)",
+        )
+        .unwrap();
+        assert_eq!(theme.colors().background, Color::rgb(0xfd, 0xf6, 0xe3));
+        assert_eq!(theme.colors().ahead, ColorScheme::default().ahead);
+        assert_eq!(theme.fsize(), (50, 20));
+        assert!(!theme.layout().inline_splits);
+        assert_eq!(theme.warnings(), ["line 6: unknown key `ringtone`"]);
+    }
+}
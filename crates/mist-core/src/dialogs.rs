@@ -6,8 +6,9 @@
 use crate::config::Config;
 use crate::parse::MsfParser;
 use crate::timer::Run;
+use crate::Error;
 use std::fs::File;
-use std::io::{BufReader, Error};
+use std::io::BufReader;
 use tinyfiledialogs::{
     message_box_ok, message_box_yes_no, open_file_dialog, save_file_dialog_with_filter,
     MessageBoxIcon, YesNo,
@@ -99,24 +100,20 @@ pub fn open_run() -> Result<Option<(Run, String)>, Error> {
 /// * If the user does not select a file.
 /// * If the file selected cannot be parsed into a [`Config`].
 #[cfg(feature = "config")]
-pub fn open_config() -> Result<Option<Config>, String> {
+pub fn open_config() -> Result<Option<Config>, Error> {
     loop {
         match get_file("Open a config file", "*.cfg") {
-            Some(ref p) => {
-                let f = File::open(p).map_err(|e| e.to_string())?;
-                let config: Result<Config, String> =
-                    ron::de::from_reader(f).map_err(|e| e.to_string());
-                match config {
-                    Ok(c) => {
-                        return Ok(Some(c));
-                    }
-                    Err(_) => {
-                        if !try_again() {
-                            return Ok(None);
-                        }
+            Some(ref p) => match Config::open_path(p) {
+                Ok(c) => {
+                    return Ok(Some(c));
+                }
+                Err(e @ Error::Io(_)) => return Err(e),
+                Err(_) => {
+                    if !try_again() {
+                        return Ok(None);
                     }
                 }
-            }
+            },
             None => return Ok(None),
         }
     }
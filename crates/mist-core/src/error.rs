@@ -0,0 +1,94 @@
+//! The error type shared by everything in mist-core.
+#[cfg(feature = "lss")]
+use crate::parse::LssError;
+use std::fmt;
+#[cfg(feature = "plugins")]
+use std::path::PathBuf;
+
+#[derive(Debug)]
+/// Errors that can be returned by mist-core.
+pub enum Error {
+    /// A file could not be opened, read or written.
+    Io(std::io::Error),
+    /// A split or config file could not be parsed.
+    ///
+    /// `line` and `col` are the position of the problem in the file, starting at 1.
+    Parse {
+        line: usize,
+        col: usize,
+        msg: String,
+    },
+    /// A split or config file could not be serialized.
+    Serialize(String),
+    /// The file that was read from was empty.
+    EmptyFile,
+    /// The split or config file is of a version newer than this version of mist-core knows about.
+    UnsupportedVersion(u32),
+    /// The requested system font could not be found. Contains the name of the font.
+    FontNotFound(String),
+    /// No theme file with the given name could be found.
+    ThemeNotFound(String),
+    /// A keybind could not be turned into a key. Contains the name of the keybind.
+    InvalidKeybind(String),
+    #[cfg(feature = "lss")]
+    /// A LiveSplit split file could not be read or written.
+    Lss(LssError),
+    #[cfg(feature = "plugins")]
+    /// A plugin could not be started, sent something that isn't a command or crashed too many times.
+    Plugin { path: PathBuf, msg: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse { line, col, msg } => {
+                write!(f, "Parse error at line {}, column {}: {}", line, col, msg)
+            }
+            Error::Serialize(msg) => write!(f, "Could not serialize: {}", msg),
+            Error::EmptyFile => f.write_str("Input was empty."),
+            Error::UnsupportedVersion(v) => write!(f, "Unsupported file version {}.", v),
+            Error::FontNotFound(name) => write!(f, "Could not find system font {}.", name),
+            Error::ThemeNotFound(name) => write!(f, "Could not find theme {}.", name),
+            Error::InvalidKeybind(name) => write!(f, "{} keybind could not be parsed.", name),
+            #[cfg(feature = "lss")]
+            Error::Lss(e) => write!(f, "{}", e),
+            #[cfg(feature = "plugins")]
+            Error::Plugin { path, msg } => write!(f, "Plugin {} {}", path.display(), msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            #[cfg(feature = "lss")]
+            Error::Lss(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ron::Error> for Error {
+    fn from(e: ron::Error) -> Self {
+        Error::Parse {
+            line: e.position.line,
+            col: e.position.col,
+            msg: e.code.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "lss")]
+impl From<LssError> for Error {
+    fn from(e: LssError) -> Self {
+        Error::Lss(e)
+    }
+}
@@ -0,0 +1,197 @@
+//! Let other programs follow and control the timer over a unix socket.
+//!
+//! Every [`StateChange`] is sent to each connected client, and clients send [`Command`]s back,
+//! both as lines of JSON as described in [`protocol`](crate::protocol).
+//! A command that can't be understood is answered with `{"error":"..."}`.
+use crate::protocol::{encode_events, Command};
+use crate::timer::state::{StateChange, StateChangeRequest};
+use crate::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// A command sent to mist by an ipc client. The same as [`Command`], which plugins use too.
+pub type IpcCommand = Command;
+
+struct Client {
+    stream: UnixStream,
+    buf: Vec<u8>,
+}
+
+/// A unix socket server that broadcasts timer events and collects commands.
+///
+/// Nothing here blocks, so it is meant to be checked once a frame: call [`poll`](IpcServer::poll)
+/// before updating the [`RunState`](crate::timer::state::RunState) and
+/// [`broadcast`](IpcServer::broadcast) with the changes it returns.
+/// Clients that disconnect or stop reading are dropped.
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    clients: Vec<Client>,
+}
+
+impl IpcServer {
+    /// Start listening on the socket at `path`.
+    ///
+    /// A stale socket file left at `path` by a previous run is removed first.
+    ///
+    /// # Errors
+    ///
+    /// * If the socket cannot be created, i.e. the directory doesn't exist or another mist is using it.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_owned();
+        if path.exists() && UnixStream::connect(&path).is_err() {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(IpcServer {
+            listener,
+            path,
+            clients: vec![],
+        })
+    }
+    /// Get the path mist uses for its socket if none is given.
+    ///
+    /// This is `mist.sock` in `$XDG_RUNTIME_DIR`, or in the temp directory if that isn't set.
+    pub fn default_path() -> PathBuf {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("mist.sock")
+    }
+    /// Get the path of the socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Accept any new clients and read the commands they have sent since the last poll.
+    pub fn poll(&mut self) -> Vec<StateChangeRequest> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buf: vec![],
+                });
+            }
+        }
+        let mut requests = vec![];
+        self.clients.retain_mut(|client| {
+            let mut chunk = [0; 512];
+            loop {
+                match client.stream.read(&mut chunk) {
+                    Ok(0) => return false,
+                    Ok(n) => client.buf.extend_from_slice(&chunk[..n]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => return false,
+                }
+            }
+            while let Some(end) = client.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = client.buf.drain(..=end).collect();
+                let line = &line[..end];
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                match serde_json::from_slice::<Command>(line) {
+                    Ok(cmd) => requests.push(cmd.into()),
+                    Err(e) => {
+                        let msg = serde_json::json!({ "error": e.to_string() });
+                        if send(&mut client.stream, &format!("{}\n", msg)).is_err() {
+                            return false;
+                        }
+                    }
+                }
+            }
+            true
+        });
+        requests
+    }
+    /// Send each change (except [`StateChange::None`]) to every client.
+    pub fn broadcast(&mut self, changes: &[StateChange]) {
+        let out = encode_events(changes);
+        if out.is_empty() {
+            return;
+        }
+        self.clients
+            .retain_mut(|client| send(&mut client.stream, &out).is_ok());
+    }
+}
+
+// a client that can't take the whole message right away is not keeping up, so it gets dropped
+fn send(stream: &mut UnixStream, msg: &str) -> std::io::Result<()> {
+    stream.write_all(msg.as_bytes())
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mist-test-{}-{}.sock", name, std::process::id()))
+    }
+
+    // the server doesn't block, so give the client's writes a moment to arrive
+    fn poll_until(server: &mut IpcServer, count: usize) -> Vec<StateChangeRequest> {
+        let mut requests = vec![];
+        for _ in 0..100 {
+            requests.append(&mut server.poll());
+            if requests.len() >= count {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        requests
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut server = IpcServer::bind(socket_path("commands")).unwrap();
+        let mut client = UnixStream::connect(server.path()).unwrap();
+        client
+            .write_all(b"{\"command\":\"split\"}\n{\"command\":\"comparison\",\"next\":false}\n")
+            .unwrap();
+        let requests = poll_until(&mut server, 2);
+        assert!(matches!(
+            requests[..],
+            [
+                StateChangeRequest::Split,
+                StateChangeRequest::Comparison(false)
+            ]
+        ));
+        client.write_all(b"{\"command\":\"fly\"}\n").unwrap();
+        poll_until(&mut server, 1);
+        let mut line = String::new();
+        BufReader::new(&client).read_line(&mut line).unwrap();
+        assert!(line.starts_with("{\"error\":"));
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let mut server = IpcServer::bind(socket_path("broadcast")).unwrap();
+        let client = UnixStream::connect(server.path()).unwrap();
+        poll_until(&mut server, 0);
+        server.broadcast(&[
+            StateChange::None,
+            StateChange::EnterSplit {
+                idx: 1,
+                name: "two".to_owned(),
+                pb: 1000,
+                gold: 900,
+            },
+            StateChange::Pause,
+        ]);
+        let mut lines = BufReader::new(&client).lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            r#"{"event":"enter_split","idx":1,"name":"two","pb":1000,"gold":900}"#
+        );
+        assert_eq!(lines.next().unwrap().unwrap(), r#"{"event":"pause"}"#);
+    }
+}
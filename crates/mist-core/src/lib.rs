@@ -1,6 +1,16 @@
+pub mod analysis;
 #[cfg(feature = "config")]
 pub mod config;
 #[cfg(feature = "dialogs")]
 pub mod dialogs;
+mod error;
+pub use error::Error;
+#[cfg(all(feature = "ipc", unix))]
+pub mod ipc;
 pub mod parse;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod protocol;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod timer;
@@ -1,26 +1,169 @@
-use crate::run::Run;
-use quick_xml::{events::Event, Reader};
-use std::io::BufRead;
+use crate::timer::{format::readable_to_ms, Attempt, AttemptEnd, Run};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use std::fmt;
+use std::io::{BufRead, Write};
 
-fn str_to_ms(tm: &String) -> u128 {
-    if tm == "00:00:00" || tm == "" {
-        return 0;
+#[derive(Debug)]
+/// Errors that can occur while reading or writing a LiveSplit split file.
+pub enum LssError {
+    /// The file is not well-formed XML or could not be read or written.
+    Xml(quick_xml::Error),
+    /// A time in the file is not a valid LiveSplit time span.
+    InvalidTime(String),
+    /// The file has no `Run` element, so it is not a LiveSplit split file.
+    NotLiveSplit,
+}
+
+impl fmt::Display for LssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LssError::Xml(e) => write!(f, "LiveSplit file error: {}", e),
+            LssError::InvalidTime(t) => write!(f, "Invalid time in LiveSplit file: {}", t),
+            LssError::NotLiveSplit => f.write_str("Not a LiveSplit split file."),
+        }
+    }
+}
+
+impl std::error::Error for LssError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LssError::Xml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<quick_xml::Error> for LssError {
+    fn from(e: quick_xml::Error) -> Self {
+        LssError::Xml(e)
+    }
+}
+
+// parses livesplit's time span format, [d.]hh:mm:ss[.fffffff], truncated to milliseconds.
+// empty times are 0, which mist treats as no time
+fn str_to_ms(tm: &str) -> Result<u128, LssError> {
+    let tm = tm.trim();
+    if tm.is_empty() {
+        return Ok(0);
     }
-    let hr = &tm[0..2].parse::<u128>().unwrap();
-    let min = &tm[3..5].parse::<u128>().unwrap();
-    let sec = &tm[6..8].parse::<u128>().unwrap();
-    let ms = &tm[9..12].parse::<u128>().unwrap();
-    return ms + (sec * 1000) + (min * 60000) + (hr * 3600000);
+    let invalid = || LssError::InvalidTime(tm.to_owned());
+    if tm.matches(':').count() != 2 {
+        return Err(invalid());
+    }
+    let hours = tm.split(':').next().unwrap_or_default();
+    let (days, rest) = match hours.split_once('.') {
+        Some((d, _)) if !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()) => (
+            d.parse::<u128>().map_err(|_| invalid())?,
+            &tm[d.len() + 1..],
+        ),
+        Some(_) => return Err(invalid()),
+        None => (0, tm),
+    };
+    readable_to_ms(rest)
+        .and_then(|ms| days.checked_mul(86_400_000)?.checked_add(ms))
+        .ok_or_else(invalid)
+}
+
+// converts livesplit's MM/dd/yyyy HH:mm:ss (in UTC) to milliseconds since the unix epoch
+fn str_to_timestamp(tm: &str) -> Option<u128> {
+    let (date, time) = tm.trim().split_once(' ')?;
+    let date: Vec<i64> = date
+        .split('/')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<u128> = time
+        .split(':')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    if date.len() != 3 || time.len() != 3 {
+        return None;
+    }
+    let (month, day, year) = (date[0], date[1], date[2]);
+    // civil date to days, from http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    if days < 0 {
+        return None;
+    }
+    Some(((days as u128 * 86_400) + time[0] * 3600 + time[1] * 60 + time[2]) * 1000)
+}
+
+// livesplit writes times as hh:mm:ss.fffffff, with a day count in front if there are more than 24 hours
+fn ms_to_str(ms: u128) -> String {
+    let days = ms / 86_400_000;
+    let hr = (ms / 3_600_000) % 24;
+    let min = (ms / 60_000) % 60;
+    let sec = (ms / 1000) % 60;
+    let frac = (ms % 1000) * 10_000;
+    if days > 0 {
+        format!("{}.{:02}:{:02}:{:02}.{:07}", days, hr, min, sec, frac)
+    } else {
+        format!("{:02}:{:02}:{:02}.{:07}", hr, min, sec, frac)
+    }
+}
+
+// converts milliseconds since the unix epoch to livesplit's MM/dd/yyyy HH:mm:ss (in UTC)
+fn timestamp_to_str(ms: u128) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    // days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:02}/{:02}/{:04} {:02}:{:02}:{:02}",
+        month,
+        day,
+        year,
+        time / 3600,
+        (time / 60) % 60,
+        time % 60
+    )
 }
 
 /// Constructs a [`Run`] from a LiveSplit split file.
 ///
 /// Attempts to retrieve the relevant information from LiveSplit's XML-based split file
-/// in order to construct a mist [`Run`]. Any info that cannot be retrieved is zeroed.
+/// in order to construct a mist [`Run`]. Any info that is not present in the file is zeroed.
 pub struct LssParser<R: BufRead> {
     reader: R,
 }
 
+#[derive(Default)]
+struct LssSegment {
+    name: String,
+    pb: u128,
+    game_pb: u128,
+    gold: u128,
+    game_gold: u128,
+    // attempt id, segment time and game time segment of every entry in the segment history
+    history: Vec<(i64, Option<u128>, Option<u128>)>,
+}
+
+struct LssAttempt {
+    id: i64,
+    started: u128,
+    finished: bool,
+    time: u128,
+    game_time: u128,
+    pause_time: u128,
+}
+
 impl<R: BufRead> LssParser<R> {
     /// Create a new LssParser from the reader. Reader must implement [`BufRead`].
     pub fn new(reader: R) -> Self {
@@ -28,100 +171,643 @@ impl<R: BufRead> LssParser<R> {
     }
     /// Retrieve the information from the reader to create a [`Run`].
     ///
-    /// Returns a [`Run`] with all of the fields that were found filled in. This can return an empty [`Run`] if the LiveSplit file
-    /// was malformed or missing information.
-    pub fn parse(&mut self) -> Run {
+    /// Returns a [`Run`] with all of the fields that were found filled in. Segments are read one at a time,
+    /// so a segment with missing times still lines up with its name. Attempt history is rebuilt from the
+    /// `AttemptHistory` element and the segment history entries with matching ids.
+    ///
+    /// # Errors
+    ///
+    /// * If the reader does not contain well-formed XML.
+    /// * If a time in the file is not a valid time span.
+    /// * If there is no `Run` element in the file.
+    pub fn parse(&mut self) -> Result<Run, LssError> {
         let mut run = Run::empty();
 
         let mut reader = Reader::from_reader(&mut self.reader);
         reader.check_end_names(false);
 
         let mut buffer = vec![];
-        let mut buffer2 = vec![];
-        let mut time_str = String::new();
-        let mut splits = vec![];
-        let mut pb_times = vec![];
-        let mut gold_times = vec![];
-        let mut sum_times = vec![];
-        let mut segment_sum: (u128, u128) = (0, 0);
-        let mut pb = 0;
+        let mut path: Vec<Vec<u8>> = vec![];
+        let mut text = String::new();
+        let mut found_run = false;
+        let mut segments: Vec<LssSegment> = vec![];
+        let mut attempts: Vec<LssAttempt> = vec![];
+        // whether the current split time is the personal best rather than some other comparison
+        let mut pb_split = false;
 
         loop {
-            match reader.read_event(&mut buffer) {
-                Ok(Event::Start(ref e)) => match e.name() {
-                    b"GameName" => {
-                        run.set_game_title(
-                            reader
-                                .read_text(b"GameName", &mut buffer2)
-                                .unwrap_or("".to_owned()),
-                        );
+            let (start, end) = match reader.read_event(&mut buffer)? {
+                Event::Start(e) => (Some(e.into_owned()), false),
+                Event::Empty(e) => (Some(e.into_owned()), true),
+                Event::Text(e) => {
+                    text.push_str(&e.unescape_and_decode(&reader)?);
+                    continue;
+                }
+                Event::End(_) => (None, true),
+                Event::Eof => break,
+                _ => continue,
+            };
+            if let Some(e) = start {
+                path.push(e.name().to_vec());
+                text.clear();
+                let attr = |name: &[u8]| -> Result<Option<String>, LssError> {
+                    for a in e.attributes() {
+                        let a = a?;
+                        if a.key == name {
+                            return Ok(Some(a.unescape_and_decode_value(&reader)?));
+                        }
                     }
-                    b"CategoryName" => {
-                        run.set_category(
-                            reader
-                                .read_text(b"CategoryName", &mut buffer2)
-                                .unwrap_or("".to_owned()),
-                        );
+                    Ok(None)
+                };
+                let p: Vec<&[u8]> = path.iter().map(|n| n.as_slice()).collect();
+                match p[..] {
+                    [b"Run"] => found_run = true,
+                    [b"Run", b"AttemptHistory", b"Attempt"] => attempts.push(LssAttempt {
+                        id: attr(b"id")?.and_then(|i| i.parse().ok()).unwrap_or(0),
+                        started: attr(b"started")?
+                            .and_then(|t| str_to_timestamp(&t))
+                            .unwrap_or(0),
+                        finished: false,
+                        time: 0,
+                        game_time: 0,
+                        pause_time: 0,
+                    }),
+                    [b"Run", b"Segments", b"Segment"] => segments.push(LssSegment::default()),
+                    [b"Run", b"Segments", b"Segment", b"SplitTimes", b"SplitTime"] => {
+                        pb_split = attr(b"name")?.as_deref() == Some("Personal Best");
                     }
-                    b"Offset" => {
-                        let mut off_str = reader
-                            .read_text(b"Offset", &mut buffer2)
-                            .unwrap_or("".to_owned());
-                        off_str.remove(0);
-                        match str_to_ms(&off_str) {
-                            0 => {}
-                            t => run.set_offset(Some(t)),
+                    [b"Run", b"Segments", b"Segment", b"SegmentHistory", b"Time"] => {
+                        let id = attr(b"id")?.and_then(|i| i.parse().ok()).unwrap_or(0);
+                        if let Some(seg) = segments.last_mut() {
+                            seg.history.push((id, None, None));
                         }
                     }
-                    b"Name" => {
-                        splits.push(
-                            reader
-                                .read_text(b"Name", &mut buffer2)
-                                .unwrap_or("".to_owned()),
-                        );
+                    _ => {}
+                }
+            }
+            if end {
+                let p: Vec<&[u8]> = path.iter().map(|n| n.as_slice()).collect();
+                match p[..] {
+                    [b"Run", b"GameName"] => run.set_game_title(&text),
+                    [b"Run", b"CategoryName"] => run.set_category(&text),
+                    // mist can only represent negative offsets (a delay before the first split starts)
+                    [b"Run", b"Offset"] => {
+                        if let Some(off) = text.trim().strip_prefix('-') {
+                            match str_to_ms(off)? {
+                                0 => {}
+                                t => run.set_offset(Some(t)),
+                            }
+                        }
                     }
-                    b"RealTime" => {
-                        time_str = reader
-                            .read_text(b"RealTime", &mut buffer2)
-                            .unwrap_or("".to_owned());
+                    [b"Run", b"AttemptCount"] => {
+                        run.set_attempt_count(text.trim().parse().unwrap_or(0));
                     }
-                    b"SegmentHistory" => {
-                        segment_sum = (0, 0);
+                    [b"Run", b"AttemptHistory", b"Attempt", b"RealTime"] => {
+                        if let Some(a) = attempts.last_mut() {
+                            a.time = str_to_ms(&text)?;
+                            a.finished |= a.time != 0;
+                        }
                     }
-                    _ => {}
-                },
-                Ok(Event::End(ref e)) => match e.name() {
-                    b"SplitTime" => match str_to_ms(&time_str) {
-                        0 => {}
-                        t => {
-                            pb_times.push(t);
-                            pb += t;
+                    [b"Run", b"AttemptHistory", b"Attempt", b"GameTime"] => {
+                        if let Some(a) = attempts.last_mut() {
+                            a.game_time = str_to_ms(&text)?;
+                            a.finished |= a.game_time != 0;
                         }
-                    },
-                    b"BestSegmentTime" => match str_to_ms(&time_str) {
-                        0 => {}
-                        t => {
-                            gold_times.push(t);
+                    }
+                    [b"Run", b"AttemptHistory", b"Attempt", b"PauseTime"] => {
+                        if let Some(a) = attempts.last_mut() {
+                            a.pause_time = str_to_ms(&text)?;
                         }
-                    },
-                    b"Time" => {
-                        segment_sum.0 += 1;
-                        segment_sum.1 += str_to_ms(&time_str);
                     }
-                    b"SegmentHistory" => {
-                        sum_times.push(segment_sum);
+                    [b"Run", b"Segments", b"Segment", ref rest @ ..] => {
+                        let seg = match segments.last_mut() {
+                            Some(s) => s,
+                            None => continue,
+                        };
+                        match rest {
+                            [b"Name"] => seg.name = text.clone(),
+                            [b"SplitTimes", b"SplitTime", b"RealTime"] if pb_split => {
+                                seg.pb = str_to_ms(&text)?;
+                            }
+                            [b"SplitTimes", b"SplitTime", b"GameTime"] if pb_split => {
+                                seg.game_pb = str_to_ms(&text)?;
+                            }
+                            [b"BestSegmentTime", b"RealTime"] => seg.gold = str_to_ms(&text)?,
+                            [b"BestSegmentTime", b"GameTime"] => {
+                                seg.game_gold = str_to_ms(&text)?;
+                            }
+                            [b"SegmentHistory", b"Time", b"RealTime"] => {
+                                if let Some(entry) = seg.history.last_mut() {
+                                    entry.1 = match str_to_ms(&text)? {
+                                        0 => None,
+                                        t => Some(t),
+                                    };
+                                }
+                            }
+                            [b"SegmentHistory", b"Time", b"GameTime"] => {
+                                if let Some(entry) = seg.history.last_mut() {
+                                    entry.2 = match str_to_ms(&text)? {
+                                        0 => None,
+                                        t => Some(t),
+                                    };
+                                }
+                            }
+                            _ => {}
+                        }
                     }
                     _ => {}
-                },
-                Ok(Event::Eof) => break,
-                _ => {}
+                }
+                path.pop();
+                text.clear();
             }
         }
-        run.set_gold_times(&gold_times);
+        if !found_run {
+            return Err(LssError::NotLiveSplit);
+        }
+        Ok(Self::build_run(run, &segments, &attempts))
+    }
+
+    fn build_run(mut run: Run, segments: &[LssSegment], attempts: &[LssAttempt]) -> Run {
+        let len = segments.len();
+        // split times are cumulative in livesplit files but mist stores segment times.
+        // missing split times stay empty and the next segment takes up their time instead
+        let to_segments = |cumulative: Vec<u128>| -> (Vec<u128>, u128) {
+            let mut last = 0;
+            let times = cumulative
+                .iter()
+                .map(|&t| {
+                    if t == 0 {
+                        0
+                    } else {
+                        let seg = t.saturating_sub(last);
+                        last = t;
+                        seg
+                    }
+                })
+                .collect();
+            (times, cumulative.last().copied().unwrap_or(0))
+        };
+        let (pb_times, pb) = to_segments(segments.iter().map(|s| s.pb).collect());
+        let (game_pb_times, game_pb) = to_segments(segments.iter().map(|s| s.game_pb).collect());
+        run.set_splits(&segments.iter().map(|s| s.name.clone()).collect());
         run.set_pb_times(&pb_times);
-        run.set_sum_times(&sum_times);
-        run.set_splits(&splits);
         run.set_pb(pb);
+        run.set_game_pb_times(&game_pb_times);
+        run.set_game_pb(game_pb);
+        run.set_gold_times(&segments.iter().map(|s| s.gold).collect());
+        run.set_game_gold_times(&segments.iter().map(|s| s.game_gold).collect());
+        run.set_sum_times(
+            &segments
+                .iter()
+                .map(|s| {
+                    s.history
+                        .iter()
+                        .filter_map(|&(_, t, _)| t)
+                        .fold((0, 0), |(n, sum), t| (n + 1, sum + t))
+                })
+                .collect(),
+        );
+
+        let mut history = vec![];
+        let mut resets = vec![0; len];
+        let mut finished = 0;
+        for attempt in attempts {
+            // an attempt passed every split that has a history entry with its id, even if it was skipped
+            let times: Vec<Option<(Option<u128>, Option<u128>)>> = segments
+                .iter()
+                .map(|s| {
+                    s.history
+                        .iter()
+                        .find(|&&(id, _, _)| id == attempt.id)
+                        .map(|&(_, t, g)| (t, g))
+                })
+                .collect();
+            let passed = if attempt.finished {
+                len
+            } else {
+                times.iter().rposition(|t| t.is_some()).map_or(0, |i| i + 1)
+            };
+            let end = if attempt.finished {
+                finished += 1;
+                AttemptEnd::Finished
+            } else {
+                if passed < len {
+                    resets[passed] += 1;
+                }
+                AttemptEnd::Reset
+            };
+            let mut recorded = Attempt::new(
+                attempt.started,
+                end,
+                &times[..passed]
+                    .iter()
+                    .map(|t| t.and_then(|t| t.0))
+                    .collect(),
+                attempt.pause_time,
+            );
+            recorded.set_time(attempt.time);
+            recorded.set_game_time(attempt.game_time);
+            // livesplit always has game times, but they are only worth keeping if there are any
+            let game_segments: Vec<Option<u128>> = times[..passed]
+                .iter()
+                .map(|t| t.and_then(|t| t.1))
+                .collect();
+            if game_segments.iter().any(Option::is_some) {
+                recorded.set_game_segments(&game_segments);
+            }
+            history.push(recorded);
+        }
+        run.set_attempt_history(&history);
+        run.set_reset_counts(&resets);
+        run.set_finished_count(finished);
         run
     }
 }
+
+#[derive(Default)]
+/// Writes a [`Run`] to a LiveSplit split file.
+///
+/// Personal best times are written as cumulative split times like LiveSplit expects, and attempt
+/// history is written along with each segment's history if the run has any.
+pub struct LssWriter {}
+
+impl LssWriter {
+    /// Create a new LssWriter.
+    pub fn new() -> Self {
+        LssWriter {}
+    }
+    /// Write the given run to the given writer as a LiveSplit XML document.
+    ///
+    /// # Errors
+    ///
+    /// * If the writer cannot be written to.
+    pub fn write<W: Write>(&self, run: &Run, writer: W) -> Result<(), LssError> {
+        let mut xml = LssXml {
+            writer: Writer::new_with_indent(writer, b' ', 2),
+        };
+        xml.event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)))?;
+        xml.start(BytesStart::borrowed_name(b"Run").with_attributes(vec![("version", "1.7.0")]))?;
+        xml.empty(BytesStart::borrowed_name(b"GameIcon"))?;
+        xml.text_element("GameName", run.game_title())?;
+        xml.text_element("CategoryName", run.category())?;
+        xml.start(BytesStart::borrowed_name(b"Metadata"))?;
+        xml.empty(BytesStart::borrowed_name(b"Run").with_attributes(vec![("id", "")]))?;
+        xml.start(
+            BytesStart::borrowed_name(b"Platform").with_attributes(vec![("usesEmulator", "False")]),
+        )?;
+        xml.end("Platform")?;
+        xml.text_element("Region", "")?;
+        xml.empty(BytesStart::borrowed_name(b"Variables"))?;
+        xml.end("Metadata")?;
+        let offset = match run.offset() {
+            Some(t) => format!("-{}", ms_to_str(t)),
+            None => "00:00:00".to_owned(),
+        };
+        xml.text_element("Offset", &offset)?;
+        xml.text_element("AttemptCount", &run.attempt_count().to_string())?;
+
+        // livesplit attempt ids start at 1
+        xml.start(BytesStart::borrowed_name(b"AttemptHistory"))?;
+        for (idx, attempt) in run.attempt_history().iter().enumerate() {
+            let id = (idx + 1).to_string();
+            let started = timestamp_to_str(attempt.started());
+            let ended = timestamp_to_str(attempt.started() + attempt.time() + attempt.pause_time());
+            xml.start(BytesStart::borrowed_name(b"Attempt").with_attributes(vec![
+                ("id", id.as_str()),
+                ("started", started.as_str()),
+                ("isStartedSynced", "False"),
+                ("ended", ended.as_str()),
+                ("isEndedSynced", "False"),
+            ]))?;
+            if attempt.end() == AttemptEnd::Finished {
+                xml.times(attempt.time(), attempt.game_time())?;
+            }
+            if attempt.pause_time() != 0 {
+                xml.text_element("PauseTime", &ms_to_str(attempt.pause_time()))?;
+            }
+            xml.end("Attempt")?;
+        }
+        xml.end("AttemptHistory")?;
+
+        xml.start(BytesStart::borrowed_name(b"Segments"))?;
+        let mut split_time = 0;
+        let mut game_split_time = 0;
+        for (idx, name) in run.splits().iter().enumerate() {
+            xml.start(BytesStart::borrowed_name(b"Segment"))?;
+            xml.text_element("Name", name)?;
+            xml.empty(BytesStart::borrowed_name(b"Icon"))?;
+
+            xml.start(BytesStart::borrowed_name(b"SplitTimes"))?;
+            xml.start(
+                BytesStart::borrowed_name(b"SplitTime")
+                    .with_attributes(vec![("name", "Personal Best")]),
+            )?;
+            // runs made in the split tool may have fewer times than splits, or no game times at all
+            let pb = run.pb_times().get(idx).copied().unwrap_or(0);
+            let game_pb = run.game_pb_times().get(idx).copied().unwrap_or(0);
+            split_time += pb;
+            game_split_time += game_pb;
+            xml.times(
+                if pb == 0 { 0 } else { split_time },
+                if game_pb == 0 { 0 } else { game_split_time },
+            )?;
+            xml.end("SplitTime")?;
+            xml.end("SplitTimes")?;
+
+            xml.start(BytesStart::borrowed_name(b"BestSegmentTime"))?;
+            xml.times(
+                run.gold_times().get(idx).copied().unwrap_or(0),
+                run.game_gold_times().get(idx).copied().unwrap_or(0),
+            )?;
+            xml.end("BestSegmentTime")?;
+
+            xml.start(BytesStart::borrowed_name(b"SegmentHistory"))?;
+            for (id, attempt) in run.attempt_history().iter().enumerate() {
+                let id = (id + 1).to_string();
+                let game = attempt.game_segments().get(idx).copied().flatten();
+                match attempt.segments().get(idx) {
+                    Some(Some(t)) => {
+                        xml.start(
+                            BytesStart::borrowed_name(b"Time")
+                                .with_attributes(vec![("id", id.as_str())]),
+                        )?;
+                        xml.times(*t, game.unwrap_or(0))?;
+                        xml.end("Time")?;
+                    }
+                    // livesplit marks skipped segments with an empty time
+                    Some(None) => {
+                        xml.empty(
+                            BytesStart::borrowed_name(b"Time")
+                                .with_attributes(vec![("id", id.as_str())]),
+                        )?;
+                    }
+                    None => {}
+                }
+            }
+            xml.end("SegmentHistory")?;
+            xml.end("Segment")?;
+        }
+        xml.end("Segments")?;
+        xml.empty(BytesStart::borrowed_name(b"AutoSplitterSettings"))?;
+        xml.end("Run")?;
+        Ok(())
+    }
+}
+
+struct LssXml<W: Write> {
+    writer: Writer<W>,
+}
+
+impl<W: Write> LssXml<W> {
+    fn event(&mut self, event: Event) -> Result<(), LssError> {
+        Ok(self.writer.write_event(event)?)
+    }
+    fn start(&mut self, start: BytesStart) -> Result<(), LssError> {
+        self.event(Event::Start(start))
+    }
+    fn empty(&mut self, empty: BytesStart) -> Result<(), LssError> {
+        self.event(Event::Empty(empty))
+    }
+    fn end(&mut self, name: &str) -> Result<(), LssError> {
+        self.event(Event::End(BytesEnd::borrowed(name.as_bytes())))
+    }
+    fn text_element(&mut self, name: &str, text: &str) -> Result<(), LssError> {
+        self.start(BytesStart::borrowed_name(name.as_bytes()))?;
+        self.event(Event::Text(BytesText::from_plain_str(text)))?;
+        self.end(name)
+    }
+    // times of 0 are empty, so they are left out
+    fn times(&mut self, real: u128, game: u128) -> Result<(), LssError> {
+        if real != 0 {
+            self.text_element("RealTime", &ms_to_str(real))?;
+        }
+        if game != 0 {
+            self.text_element("GameTime", &ms_to_str(game))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_run() -> Run {
+        let mut run = Run::new(
+            "game & stuff",
+            "any%",
+            Some(1500),
+            6000,
+            &vec!["one".to_owned(), "two".to_owned(), "<three>".to_owned()],
+            &vec![1000, 2000, 3000],
+            &vec![900, 1800, 2700],
+            &vec![(0, 0), (0, 0), (0, 0)],
+        );
+        run.set_game_pb(5500);
+        run.set_game_pb_times(&vec![1000, 1500, 3000]);
+        run.set_game_gold_times(&vec![900, 1400, 2600]);
+        run.set_attempt_count(12);
+        run.set_reset_counts(&vec![0, 0, 0]);
+        run
+    }
+
+    #[test]
+    fn test_ms_to_str() {
+        assert_eq!(ms_to_str(3_723_456), "01:02:03.4560000");
+        assert_eq!(ms_to_str(90_000_000), "1.01:00:00.0000000");
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp_to_str(0), "01/01/1970 00:00:00");
+        assert_eq!(timestamp_to_str(1_600_000_000_000), "09/13/2020 12:26:40");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let run = test_run();
+        let mut out = vec![];
+        LssWriter::new().write(&run, &mut out).unwrap();
+        let mut parser = LssParser::new(std::io::BufReader::new(&out[..]));
+        assert_eq!(parser.parse().unwrap(), run);
+    }
+
+    #[test]
+    fn test_round_trip_history() {
+        let mut run = test_run();
+        let mut finished = Attempt::new(
+            1_600_000_000_000,
+            AttemptEnd::Finished,
+            &vec![Some(1000), Some(2000), Some(3000)],
+            500,
+        );
+        finished.set_time(6000);
+        finished.set_game_segments(&vec![Some(1000), Some(1500), Some(3000)]);
+        finished.set_game_time(5500);
+        let mut reset = Attempt::new(
+            1_600_000_100_000,
+            AttemptEnd::Reset,
+            &vec![Some(900), None],
+            0,
+        );
+        reset.set_game_segments(&vec![Some(800), None]);
+        run.set_attempt_history(&vec![finished, reset]);
+        run.set_sum_times(&vec![(2, 1900), (1, 2000), (1, 3000)]);
+        run.set_reset_counts(&vec![0, 0, 1]);
+        run.set_finished_count(1);
+        let mut out = vec![];
+        LssWriter::new().write(&run, &mut out).unwrap();
+        let mut parser = LssParser::new(std::io::BufReader::new(&out[..]));
+        assert_eq!(parser.parse().unwrap(), run);
+    }
+
+    #[test]
+    fn test_write_missing_times() {
+        // like a run made from scratch in the split tool
+        let mut run = Run::empty();
+        run.set_splits(&vec!["one".to_owned(), "two".to_owned()]);
+        run.set_pb_times(&vec![1000]);
+        let mut skipped = Attempt::new(0, AttemptEnd::Finished, &vec![Some(1000), None], 0);
+        skipped.set_time(2500);
+        run.add_attempt(skipped);
+        let mut out = vec![];
+        LssWriter::new().write(&run, &mut out).unwrap();
+        let mut parser = LssParser::new(std::io::BufReader::new(&out[..]));
+        let parsed = parser.parse().unwrap();
+        assert_eq!(parsed.pb_times(), &vec![1000, 0]);
+        // the skipped segment's time is still part of the finished time
+        assert_eq!(parsed.attempt_history()[0].time(), 2500);
+    }
+
+    #[test]
+    fn test_str_to_ms() {
+        assert_eq!(str_to_ms("01:02:03.4567890").unwrap(), 3_723_456);
+        assert_eq!(str_to_ms("1.01:00:00").unwrap(), 90_000_000);
+        assert_eq!(str_to_ms("00:00:01.5").unwrap(), 1500);
+        assert_eq!(str_to_ms("").unwrap(), 0);
+        assert!(str_to_ms("1:2").is_err());
+        assert!(str_to_ms("aa:00:00").is_err());
+        assert!(str_to_ms("00:00:01.ab€").is_err());
+        assert!(str_to_ms(&format!("{}.00:00:00", "9".repeat(38))).is_err());
+    }
+
+    const LIVESPLIT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <GameIcon />
+  <GameName>Some Game</GameName>
+  <CategoryName>100%</CategoryName>
+  <Metadata>
+    <Run id="" />
+    <Platform usesEmulator="False">
+    </Platform>
+    <Variables />
+  </Metadata>
+  <Offset>00:00:00</Offset>
+  <AttemptCount>3</AttemptCount>
+  <AttemptHistory>
+    <Attempt id="1" started="09/13/2020 12:26:40" isStartedSynced="True" ended="09/13/2020 13:30:00" isEndedSynced="True">
+      <RealTime>1.00:00:03.0000000</RealTime>
+    </Attempt>
+    <Attempt id="2" started="09/13/2020 14:00:00" isStartedSynced="True" ended="09/13/2020 14:10:00" isEndedSynced="True" />
+  </AttemptHistory>
+  <Segments>
+    <Segment>
+      <Name>first</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best">
+          <RealTime>00:00:01.2345678</RealTime>
+        </SplitTime>
+        <SplitTime name="Other Comparison">
+          <RealTime>00:00:09.0000000</RealTime>
+        </SplitTime>
+      </SplitTimes>
+      <BestSegmentTime>
+        <RealTime>00:00:01.0000000</RealTime>
+      </BestSegmentTime>
+      <SegmentHistory>
+        <Time id="1">
+          <RealTime>00:00:01.2345678</RealTime>
+        </Time>
+        <Time id="2">
+          <RealTime>00:00:01.0000000</RealTime>
+        </Time>
+      </SegmentHistory>
+    </Segment>
+    <Segment>
+      <Name>second</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best" />
+      </SplitTimes>
+      <BestSegmentTime />
+      <SegmentHistory>
+        <Time id="1" />
+      </SegmentHistory>
+    </Segment>
+    <Segment>
+      <Name>third</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best">
+          <RealTime>1.00:00:03.0000000</RealTime>
+        </SplitTime>
+      </SplitTimes>
+      <BestSegmentTime>
+        <RealTime>00:00:01.0000000</RealTime>
+      </BestSegmentTime>
+      <SegmentHistory>
+        <Time id="1">
+          <RealTime>1.00:00:01.7654322</RealTime>
+        </Time>
+      </SegmentHistory>
+    </Segment>
+  </Segments>
+  <AutoSplitterSettings>
+    <Name>not a segment</Name>
+  </AutoSplitterSettings>
+</Run>"#;
+
+    #[test]
+    fn test_parse_livesplit() {
+        let mut parser = LssParser::new(std::io::BufReader::new(LIVESPLIT.as_bytes()));
+        let run = parser.parse().unwrap();
+        assert_eq!(run.game_title(), "Some Game");
+        assert_eq!(run.category(), "100%");
+        assert_eq!(run.offset(), None);
+        assert_eq!(
+            run.splits(),
+            &vec!["first".to_owned(), "second".to_owned(), "third".to_owned()]
+        );
+        assert_eq!(run.pb_times(), &vec![1234, 0, 86_401_766]);
+        assert_eq!(run.pb(), 86_403_000);
+        assert_eq!(run.gold_times(), &vec![1000, 0, 1000]);
+        assert_eq!(run.sum_times(), &vec![(2, 2234), (0, 0), (1, 86_401_765)]);
+        assert_eq!(run.attempt_count(), 3);
+        assert_eq!(run.finished_count(), 1);
+        assert_eq!(run.reset_counts(), &vec![0, 1, 0]);
+        let history = run.attempt_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].started(), 1_600_000_000_000);
+        assert_eq!(history[0].end(), AttemptEnd::Finished);
+        assert_eq!(
+            history[0].segments(),
+            &vec![Some(1234), None, Some(86_401_765)]
+        );
+        assert_eq!(history[1].end(), AttemptEnd::Reset);
+        assert_eq!(history[1].segments(), &vec![Some(1000)]);
+    }
+
+    #[test]
+    fn test_invalid_offset() {
+        let lss = "<Run><Offset>-00:00:01.ab€</Offset><Segments /></Run>";
+        let mut parser = LssParser::new(std::io::BufReader::new(lss.as_bytes()));
+        assert!(matches!(parser.parse(), Err(LssError::InvalidTime(_))));
+    }
+
+    #[test]
+    fn test_not_livesplit() {
+        let mut parser = LssParser::new(std::io::BufReader::new("<Other></Other>".as_bytes()));
+        assert!(matches!(parser.parse(), Err(LssError::NotLiveSplit)));
+    }
+}
@@ -5,4 +5,4 @@ pub use msf::MsfParser;
 #[cfg(feature = "lss")]
 mod lss;
 #[cfg(feature = "lss")]
-pub use lss::LssParser;
+pub use lss::{LssError, LssParser, LssWriter};
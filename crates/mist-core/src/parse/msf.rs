@@ -1,9 +1,9 @@
 use crate::timer::Run;
+use crate::Error;
 use ron::de::from_str;
 use ron::ser::{to_writer_pretty, PrettyConfig};
 use serde::Deserialize;
 use std::io::{BufRead, Write};
-use std::str::FromStr;
 
 #[derive(Deserialize)]
 struct LegacyRun {
@@ -32,11 +32,45 @@ impl Into<Run> for LegacyRun {
     }
 }
 
+#[derive(Deserialize)]
+struct RunV1 {
+    game_title: String,
+    category: String,
+    offset: Option<u128>,
+    pb: u128,
+    splits: Vec<String>,
+    pb_times: Vec<u128>,
+    gold_times: Vec<u128>,
+    sum_times: Vec<(u128, u128)>,
+}
+
+// version 1 runs have no attempt history, so it just starts out empty.
+// the number of times the first and last splits were passed is the best guess for attempt counts
+impl From<RunV1> for Run {
+    fn from(old: RunV1) -> Run {
+        let attempts = old.sum_times.first().map_or(0, |s| s.0);
+        let finished = old.sum_times.last().map_or(0, |s| s.0);
+        let mut run = Run::new(
+            old.game_title,
+            old.category,
+            old.offset,
+            old.pb,
+            &old.splits,
+            &old.pb_times,
+            &old.gold_times,
+            &old.sum_times,
+        );
+        run.set_attempt_count(attempts);
+        run.set_finished_count(finished);
+        run
+    }
+}
+
 /// Parses the version and [`Run`] from a mist split file (msf)
 pub struct MsfParser {}
 
 impl MsfParser {
-    pub const VERSION: u8 = 1;
+    pub const VERSION: u8 = 2;
     /// Create a new MsfParser.
     pub fn new() -> Self {
         MsfParser {}
@@ -44,16 +78,16 @@ impl MsfParser {
     /// Attempt to parse a [`Run`] from the given reader. Reader must implement [`BufRead`].
     ///
     /// If the file does not specify version in the first line, it is assumed to be a legacy (i.e. not up to date) run
-    /// and is treated as such. Runs converted from legacy runs will have the new field(s) filled but zeroed.
+    /// and is treated as such. Runs converted from legacy or older versioned runs will have the new field(s) filled but zeroed.
     ///
     /// # Errors
     ///
     /// * If the reader cannot be read from or is empty.
     /// * If a [`Run`] (legacy or otherwise) cannot be parsed from the reader.
-    pub fn parse<R: BufRead>(&self, reader: R) -> Result<Run, String> {
-        let mut lines = reader.lines().map(|l| l.unwrap());
-        // TODO: better error handling
-        let ver_info = String::from_str(&lines.next().ok_or("Input was empty.")?).unwrap();
+    /// * If the file is of a version newer than this parser knows about.
+    pub fn parse<R: BufRead>(&self, reader: R) -> Result<Run, Error> {
+        let mut lines = reader.lines();
+        let ver_info = lines.next().ok_or(Error::EmptyFile)??;
         let version: u32 = match ver_info.rsplit_once(' ') {
             Some(num) => num.1.parse::<u32>().unwrap_or(0),
             None => 0,
@@ -62,27 +96,42 @@ impl MsfParser {
             let mut s = String::new();
             if version == 0 {
                 s.push_str(&ver_info);
+                s.push('\n');
             }
             for line in lines {
-                s.push_str(&line);
+                s.push_str(&line?);
                 s.push('\n');
             }
             s
         };
-        let run = self.run_sanity(if version == 0 {
-            from_str::<LegacyRun>(&data)
-                .map_err(|e| e.to_string())?
-                .into()
-        } else {
-            from_str(&data).map_err(|e| e.to_string())?
+        // the version line is not passed to ron, so error positions are one line off for versioned files
+        let located = |e: ron::Error| match Error::from(e) {
+            Error::Parse { line, col, msg } if version != 0 => Error::Parse {
+                line: line + 1,
+                col,
+                msg,
+            },
+            e => e,
+        };
+        let run = self.run_sanity(match version {
+            0 => from_str::<LegacyRun>(&data).map_err(located)?.into(),
+            1 => from_str::<RunV1>(&data).map_err(located)?.into(),
+            2 => from_str(&data).map_err(located)?,
+            v => return Err(Error::UnsupportedVersion(v)),
         });
         Ok(run)
     }
     /// Write the given run to the given writer.
-    pub fn write<W: Write>(&self, run: &Run, mut writer: W) -> Result<(), String> {
+    ///
+    /// # Errors
+    ///
+    /// * If the writer cannot be written to.
+    /// * If the run cannot be serialized.
+    pub fn write<W: Write>(&self, run: &Run, mut writer: W) -> Result<(), Error> {
         let run = self.run_sanity(run.clone());
-        writer.write(b"version 1\n").map_err(|e| e.to_string())?;
-        to_writer_pretty(&mut writer, &run, PrettyConfig::new()).map_err(|e| e.to_string())?;
+        writer.write_all(format!("version {}\n", Self::VERSION).as_bytes())?;
+        to_writer_pretty(&mut writer, &run, PrettyConfig::new())
+            .map_err(|e| Error::Serialize(e.to_string()))?;
         Ok(())
     }
 
@@ -92,6 +141,9 @@ impl MsfParser {
         let mut golds = run.gold_times().to_owned();
         let mut times = run.pb_times().to_owned();
         let mut sums = run.sum_times().to_owned();
+        let mut resets = run.reset_counts().to_owned();
+        let mut game_golds = run.game_gold_times().to_owned();
+        let mut game_times = run.game_pb_times().to_owned();
         if golds.len() < len {
             golds.resize_with(len, Default::default);
             run.set_gold_times(&golds);
@@ -104,6 +156,31 @@ impl MsfParser {
             sums.resize_with(len, Default::default);
             run.set_sum_times(&sums);
         }
+        if resets.len() < len {
+            resets.resize_with(len, Default::default);
+            run.set_reset_counts(&resets);
+        }
+        if game_golds.len() < len {
+            game_golds.resize_with(len, Default::default);
+            run.set_game_gold_times(&game_golds);
+        }
+        if game_times.len() < len {
+            game_times.resize_with(len, Default::default);
+            run.set_game_pb_times(&game_times);
+        }
+        if run
+            .custom_comparisons()
+            .iter()
+            .any(|c| c.times().len() < len)
+        {
+            let mut comparisons = run.custom_comparisons().to_owned();
+            for comparison in &mut comparisons {
+                let mut times = comparison.times().to_owned();
+                times.resize_with(len, Default::default);
+                comparison.set_times(&times);
+            }
+            run.set_custom_comparisons(&comparisons);
+        }
         run
     }
 }
@@ -111,6 +188,7 @@ impl MsfParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::timer::{Attempt, AttemptEnd, CustomComparison};
     const V1RUN: &[u8] = b"version 1\n
         (
             game_title: \"test\",
@@ -131,6 +209,109 @@ mod tests {
         assert!(run.is_ok());
     }
 
+    #[test]
+    fn test_parse_v1() {
+        let reader = std::io::BufReader::new(V1RUN);
+        let parser = MsfParser::new();
+        let run = parser.parse(reader).unwrap();
+        assert_eq!(run.sum_times().to_owned(), vec![(2, 2480)]);
+        assert!(run.attempt_history().is_empty());
+        assert_eq!(run.attempt_count(), 2);
+        assert_eq!(run.finished_count(), 2);
+    }
+
+    const V2RUN: &[u8] = b"version 2\n
+        (
+            game_title: \"test\",
+            category: \"test\",
+            offset: None,
+            pb: 3000,
+            splits: [\"test\", \"test2\"],
+            pb_times: [1000, 2000],
+            gold_times: [1000, 2000],
+            sum_times: [(2, 2100), (1, 2000)],
+            attempt_history: [
+                (started: 1600000000000, end: Finished, segments: [Some(1000), Some(2000)], pause_time: 0),
+                (started: 1600000100000, end: Reset, segments: [Some(1100)], pause_time: 500),
+            ],
+            attempt_count: 3,
+            finished_count: 1,
+            reset_counts: [1, 1],
+        )";
+
+    #[test]
+    fn test_parse_v2() {
+        let reader = std::io::BufReader::new(V2RUN);
+        let parser = MsfParser::new();
+        let run = parser.parse(reader).unwrap();
+        assert_eq!(run.attempt_history().len(), 2);
+        assert_eq!(run.attempt_history()[0].end(), AttemptEnd::Finished);
+        assert_eq!(
+            run.attempt_history()[1].segments().to_owned(),
+            vec![Some(1100)]
+        );
+        assert_eq!(run.attempt_history()[1].pause_time(), 500);
+        assert_eq!(run.attempt_count(), 3);
+        assert_eq!(run.reset_counts().to_owned(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_write_attempts() {
+        let parser = MsfParser::new();
+        let mut run = parser.parse(std::io::BufReader::new(V2RUN)).unwrap();
+        run.add_attempt(Attempt::new(
+            1600000200000,
+            AttemptEnd::Reset,
+            &vec![None],
+            0,
+        ));
+        let mut out = vec![];
+        parser.write(&run, &mut out).unwrap();
+        assert!(out.starts_with(b"version 2\n"));
+        let reparsed = parser.parse(std::io::BufReader::new(&out[..])).unwrap();
+        assert_eq!(reparsed.attempt_history(), run.attempt_history());
+    }
+
+    #[test]
+    fn test_custom_comparisons() {
+        let parser = MsfParser::new();
+        let mut run = parser.parse(std::io::BufReader::new(V2RUN)).unwrap();
+        assert!(run.custom_comparisons().is_empty());
+        run.add_custom_comparison(CustomComparison::new("goal", &vec![900]));
+        let mut out = vec![];
+        parser.write(&run, &mut out).unwrap();
+        let reparsed = parser.parse(std::io::BufReader::new(&out[..])).unwrap();
+        assert_eq!(reparsed.custom_comparisons()[0].name(), "goal");
+        assert_eq!(
+            reparsed.custom_comparisons()[0].times().to_owned(),
+            vec![900, 0]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let reader = std::io::BufReader::new(&b"version 255\n()"[..]);
+        let parser = MsfParser::new();
+        assert!(matches!(
+            parser.parse(reader),
+            Err(Error::UnsupportedVersion(255))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parser = MsfParser::new();
+        assert!(matches!(
+            parser.parse(std::io::BufReader::new(&b""[..])),
+            Err(Error::EmptyFile)
+        ));
+        let reader = std::io::BufReader::new(&b"version 2\n(\n    game_title: 5,\n)"[..]);
+        match parser.parse(reader) {
+            Err(Error::Parse { line, .. }) => assert_eq!(line, 3),
+            r => panic!("expected parse error, got {:?}", r),
+        }
+    }
+
     const LEGACYRUN: &[u8] = b"(
         game_title: \"test\",
         category: \"test\",
@@ -171,5 +352,7 @@ mod tests {
         assert_eq!(run.gold_times().to_owned(), vec![1234, 0]);
         assert_eq!(run.pb_times().to_owned(), vec![1234, 0]);
         assert_eq!(run.sum_times().to_owned(), vec![(2, 1234), (0, 0)]);
+        assert_eq!(run.reset_counts().to_owned(), vec![0, 0]);
+        assert_eq!(run.game_pb_times().to_owned(), vec![0, 0]);
     }
 }
@@ -0,0 +1,410 @@
+//! Run plugins as child processes that follow and control the timer.
+//!
+//! A plugin is any executable in a plugins directory. mist starts each one with its stdin and stdout piped,
+//! writes every [`StateChange`] to its stdin and reads [`Command`]s from its stdout, one JSON object per line as
+//! described in [`protocol`](crate::protocol). Anything a plugin writes to stderr goes to mist's stderr. Plugins that
+//! can't be started, send invalid commands or crash too often are reported by [`PluginHost::take_errors`].
+//!
+//! When mist exits it closes each plugin's stdin, which is the signal for the plugin to exit. Plugins that are still
+//! running after [`SHUTDOWN_GRACE`](PluginHost::SHUTDOWN_GRACE) are killed. A plugin that exits successfully on its
+//! own is done and is left alone. One that crashes (exits with an error or is killed) is restarted after
+//! [`RESTART_DELAY`](PluginHost::RESTART_DELAY), up to [`MAX_RESTARTS`](PluginHost::MAX_RESTARTS) times, after which
+//! it is disabled.
+use crate::protocol::{encode_events, Command};
+use crate::timer::state::{StateChange, StateChangeRequest};
+use crate::Error;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as Process, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Plugin {
+    path: PathBuf,
+    child: Child,
+    // dropping the sender closes the plugin's stdin
+    events: Option<Sender<String>>,
+    // lines that aren't commands come through as the error from parsing them
+    commands: Receiver<Result<Command, String>>,
+    restarts: u32,
+}
+
+impl Plugin {
+    fn spawn(path: &Path, restarts: u32) -> Result<Self, Error> {
+        let mut child = Process::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        // both pipes are handled on their own threads so a plugin that is slow to read or write never blocks mist
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (events, event_rx) = channel::<String>();
+        thread::spawn(move || {
+            for line in event_rx {
+                if stdin.write_all(line.as_bytes()).is_err() || stdin.flush().is_err() {
+                    break;
+                }
+            }
+        });
+        let (command_tx, commands) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(l) => l,
+                    Err(_) => break,
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let cmd = serde_json::from_str::<Command>(&line).map_err(|e| e.to_string());
+                if command_tx.send(cmd).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Plugin {
+            path: path.to_owned(),
+            child,
+            events: Some(events),
+            commands,
+            restarts,
+        })
+    }
+}
+
+// a crashed plugin waiting to be started again
+struct Restart {
+    path: PathBuf,
+    restarts: u32,
+    at: Instant,
+}
+
+/// Starts, feeds and stops plugins.
+///
+/// Like the other servers it never blocks: call [`poll`](PluginHost::poll) once a frame to collect requests and
+/// restart crashed plugins, and [`broadcast`](PluginHost::broadcast) with the changes from each update.
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+    restarts: Vec<Restart>,
+    errors: Vec<Error>,
+}
+
+impl PluginHost {
+    /// How many times a plugin is restarted before it is disabled.
+    pub const MAX_RESTARTS: u32 = 3;
+    /// How long to wait before restarting a plugin that crashed.
+    pub const RESTART_DELAY: Duration = Duration::from_millis(200);
+    /// How long plugins get to exit on their own after their stdin is closed.
+    pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+    /// Start every executable in `dir`, in order of file name.
+    ///
+    /// A missing directory just means there are no plugins. Plugins that fail to start are skipped and reported by
+    /// [`take_errors`](PluginHost::take_errors).
+    ///
+    /// # Errors
+    ///
+    /// * If the directory exists but cannot be read.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        Self::load_dirs(&[dir])
+    }
+    /// Like [`load_dir`](PluginHost::load_dir), but with the executables in all of `dirs`, i.e. the ones from
+    /// `config::paths::plugin_dirs`. If more than one directory has a plugin with the
+    /// same file name, only the one in the first of them is started.
+    ///
+    /// # Errors
+    ///
+    /// * If one of the directories exists but cannot be read.
+    pub fn load_dirs<P: AsRef<Path>>(dirs: &[P]) -> Result<Self, Error> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for dir in dirs {
+            let entries = match std::fs::read_dir(dir) {
+                Ok(e) => e,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if is_executable(&path) && !paths.iter().any(|p| p.file_name() == path.file_name())
+                {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        let mut host = Self::default();
+        for path in paths {
+            match Plugin::spawn(&path, 0) {
+                Ok(p) => host.plugins.push(p),
+                Err(e) => host.errors.push(Error::Plugin {
+                    msg: format!("could not be started: {}", e),
+                    path,
+                }),
+            }
+        }
+        Ok(host)
+    }
+    /// Get the paths of the plugins that are running or waiting to be restarted after a crash.
+    pub fn plugins(&self) -> Vec<&Path> {
+        self.plugins
+            .iter()
+            .map(|p| p.path.as_path())
+            .chain(self.restarts.iter().map(|r| r.path.as_path()))
+            .collect()
+    }
+    /// Collect the requests plugins have sent since the last poll, and restart any that crashed long enough ago.
+    pub fn poll(&mut self) -> Vec<StateChangeRequest> {
+        let mut requests = vec![];
+        for plugin in &mut self.plugins {
+            for cmd in plugin.commands.try_iter() {
+                match cmd {
+                    Ok(cmd) => requests.push(StateChangeRequest::from(cmd)),
+                    Err(msg) => self.errors.push(Error::Plugin {
+                        path: plugin.path.clone(),
+                        msg: format!("sent an invalid command: {}", msg),
+                    }),
+                }
+            }
+        }
+        let mut idx = 0;
+        while idx < self.plugins.len() {
+            let status = match self.plugins[idx].child.try_wait() {
+                Ok(None) => {
+                    idx += 1;
+                    continue;
+                }
+                Ok(Some(status)) => Some(status),
+                Err(_) => None,
+            };
+            let old = self.plugins.remove(idx);
+            if status.is_some_and(|s| s.success()) {
+                continue;
+            }
+            if old.restarts >= Self::MAX_RESTARTS {
+                self.errors.push(Error::Plugin {
+                    path: old.path,
+                    msg: "crashed too many times and was disabled".to_owned(),
+                });
+                continue;
+            }
+            self.restarts.push(Restart {
+                path: old.path,
+                restarts: old.restarts + 1,
+                at: Instant::now() + Self::RESTART_DELAY,
+            });
+        }
+        let now = Instant::now();
+        let (ready, waiting): (Vec<Restart>, Vec<Restart>) =
+            self.restarts.drain(..).partition(|r| r.at <= now);
+        self.restarts = waiting;
+        for restart in ready {
+            match Plugin::spawn(&restart.path, restart.restarts) {
+                Ok(p) => self.plugins.push(p),
+                Err(e) => self.errors.push(Error::Plugin {
+                    path: restart.path,
+                    msg: format!("could not be restarted: {}", e),
+                }),
+            }
+        }
+        requests
+    }
+    /// Get the problems with plugins since the last call, oldest first.
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+    /// Send each change (except [`StateChange::None`]) to every plugin.
+    pub fn broadcast(&mut self, changes: &[StateChange]) {
+        let out = encode_events(changes);
+        if out.is_empty() {
+            return;
+        }
+        for plugin in &self.plugins {
+            if let Some(events) = &plugin.events {
+                // a plugin that has exited is restarted on the next poll, so a failed send doesn't matter
+                let _ = events.send(out.clone());
+            }
+        }
+    }
+    /// Close every plugin's stdin and wait for them to exit, killing any that take too long.
+    pub fn shutdown(&mut self) {
+        self.restarts.clear();
+        for plugin in &mut self.plugins {
+            plugin.events = None;
+        }
+        let deadline = Instant::now() + Self::SHUTDOWN_GRACE;
+        for mut plugin in self.plugins.drain(..) {
+            loop {
+                match plugin.child.try_wait() {
+                    Ok(None) if Instant::now() < deadline => {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Ok(None) => {
+                        let _ = plugin.child.kill();
+                        let _ = plugin.child.wait();
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PluginHost {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn plugin_dir(name: &str, script: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mist-plugins-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("plugin.sh");
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // not executable, so it is not a plugin
+        std::fs::write(dir.join("README"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_events_and_commands() {
+        // splits every time it is told a split was entered
+        let dir = plugin_dir(
+            "echo",
+            "#!/bin/sh\nwhile read line; do\n  case \"$line\" in\n    *enter_split*) echo '{\"command\":\"split\"}';;\n  esac\ndone\n",
+        );
+        let mut host = PluginHost::load_dir(&dir).unwrap();
+        assert_eq!(host.plugins().len(), 1);
+        host.broadcast(&[
+            StateChange::None,
+            StateChange::EnterSplit {
+                idx: 0,
+                name: "one".to_owned(),
+                pb: 0,
+                gold: 0,
+            },
+        ]);
+        let mut requests = vec![];
+        for _ in 0..200 {
+            requests.append(&mut host.poll());
+            if !requests.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(requests[..], [StateChangeRequest::Split]));
+        host.shutdown();
+        assert!(host.plugins().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_crashing_plugin_is_disabled() {
+        let dir = plugin_dir("crash", "#!/bin/sh\nexit 1\n");
+        let mut host = PluginHost::load_dir(&dir).unwrap();
+        for _ in 0..200 {
+            host.poll();
+            if host.plugins().is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(host.plugins().is_empty());
+        let errors = host.take_errors();
+        assert!(matches!(
+            &errors[..],
+            [Error::Plugin { msg, .. }] if msg.contains("crashed too many times")
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_command() {
+        let dir = plugin_dir(
+            "invalid",
+            "#!/bin/sh
+echo nonsense
+read line
+",
+        );
+        let mut host = PluginHost::load_dir(&dir).unwrap();
+        let mut errors = vec![];
+        for _ in 0..200 {
+            assert!(host.poll().is_empty());
+            errors.append(&mut host.take_errors());
+            if !errors.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(
+            &errors[..],
+            [Error::Plugin { msg, .. }] if msg.starts_with("sent an invalid command")
+        ));
+        host.shutdown();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_finished_plugin_is_not_restarted() {
+        let dir = plugin_dir("done", "#!/bin/sh\nexit 0\n");
+        let mut host = PluginHost::load_dir(&dir).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !host.plugins().is_empty() && Instant::now() < deadline {
+            host.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(host.plugins().is_empty());
+        assert!(host.restarts.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_restart_delay() {
+        let dir = plugin_dir("delay", "#!/bin/sh\nexit 1\n");
+        let mut host = PluginHost::load_dir(&dir).unwrap();
+        let _ = host.plugins[0].child.wait();
+        host.poll();
+        // the crashed plugin waits to be restarted instead of being started again right away
+        assert!(host.plugins.is_empty());
+        assert_eq!(host.restarts.len(), 1);
+        thread::sleep(PluginHost::RESTART_DELAY);
+        host.poll();
+        assert_eq!(host.plugins.len(), 1);
+        assert_eq!(host.plugins[0].restarts, 1);
+        host.shutdown();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_dir() {
+        let host = PluginHost::load_dir("/nonexistent/mist/plugins").unwrap();
+        assert!(host.plugins().is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+//! The messages mist exchanges with other programs, shared by the ipc server and plugins.
+//!
+//! mist sends each [`StateChange`] as a line of JSON, tagged with its name in `event`,
+//! e.g. `{"event":"enter_split","idx":1,"name":"Level 2","pb":51200,"gold":49870}` or `{"event":"pause"}`.
+//! Programs send [`Command`]s back the same way, one per line, e.g. `{"command":"split"}`
+//! or `{"command":"comparison","next":true}`.
+//!
+//! [`Command`] implements serde's traits, so a program written in Rust can use it directly with `serde_json`.
+#[doc(no_inline)]
+pub use crate::timer::state::StateChange;
+use crate::timer::state::StateChangeRequest;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+/// A command sent to mist to control the timer.
+pub enum Command {
+    Split,
+    Pause,
+    Reset,
+    Skip,
+    Unsplit,
+    /// Switch to the next comparison if `next` is true, otherwise the previous one.
+    Comparison {
+        next: bool,
+    },
+}
+
+impl From<Command> for StateChangeRequest {
+    fn from(cmd: Command) -> Self {
+        match cmd {
+            Command::Split => StateChangeRequest::Split,
+            Command::Pause => StateChangeRequest::Pause,
+            Command::Reset => StateChangeRequest::Reset,
+            Command::Skip => StateChangeRequest::Skip,
+            Command::Unsplit => StateChangeRequest::Unsplit,
+            Command::Comparison { next } => StateChangeRequest::Comparison(next),
+        }
+    }
+}
+
+// every change except `None` as one line of JSON each
+#[cfg(any(feature = "ipc", feature = "plugins"))]
+pub(crate) fn encode_events(changes: &[StateChange]) -> String {
+    let mut out = String::new();
+    for change in changes {
+        if let StateChange::None = change {
+            continue;
+        }
+        if let Ok(line) = serde_json::to_string(change) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(all(test, any(feature = "ipc", feature = "plugins")))]
+mod tests {
+    use super::*;
+    use crate::timer::state::SplitStatus;
+
+    #[test]
+    fn test_encoding() {
+        let out = encode_events(&[
+            StateChange::None,
+            StateChange::ExitSplit {
+                idx: 2,
+                name: "three".to_owned(),
+                status: SplitStatus::Gold,
+                time: 1234,
+                diff: -56,
+            },
+        ]);
+        assert_eq!(
+            out,
+            "{\"event\":\"exit_split\",\"idx\":2,\"name\":\"three\",\"status\":\"Gold\",\"time\":1234,\"diff\":-56}\n"
+        );
+        let cmd: Command = serde_json::from_str(r#"{"command":"comparison","next":true}"#).unwrap();
+        assert_eq!(cmd, Command::Comparison { next: true });
+    }
+}
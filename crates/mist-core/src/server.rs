@@ -0,0 +1,335 @@
+//! A server that speaks the LiveSplit Server text protocol over TCP.
+//!
+//! Tools written for LiveSplit Server connect to a port on localhost and send one command per line, like `split` or
+//! `getcurrenttime`. Commands that control the timer are turned into [`StateChangeRequest`]s, and queries are answered
+//! with a single line.
+//!
+//! Supported commands:
+//! * `starttimer`, `startorsplit`, `split`, `unsplit`, `skipsplit`, `pause`, `resume`, `reset`
+//! * `pausegametime`, `unpausegametime`, `setgametime TIME`, `initgametime` (does nothing, game time always exists)
+//! * `setcomparison NAME` (a LiveSplit comparison or one of the run's custom comparisons), `switchto realtime`,
+//!   `switchto gametime`
+//! * `getcurrenttime`, `getlastsplittime`, `getcomparisonsplittime`, `getfinaltime`, `getdelta`,
+//!   `getsplitindex`, `getcurrentsplitname`, `getprevioussplitname`, `getcurrenttimerphase`, `ping`
+//!
+//! Times are answered in the same format mist displays them, and deltas are signed. Missing times are answered with `-`.
+//! Queries see the timer as of the last update, so a query sent right after a command in the same frame does not
+//! see the effect of that command.
+use crate::timer::format::{diff_text, ms_to_readable, readable_to_ms};
+use crate::timer::state::{RunState, StateChangeRequest, TimerState};
+use crate::timer::{Clock, Comparison as Comp, TimingMethod};
+use crate::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+struct Client {
+    stream: TcpStream,
+    buf: Vec<u8>,
+}
+
+/// A LiveSplit Server compatible TCP server listening on localhost.
+///
+/// Like [`IpcServer`](crate::ipc::IpcServer) (where available) it never blocks, and should be polled once a frame
+/// before the [`RunState`] is updated.
+pub struct LiveSplitServer {
+    listener: TcpListener,
+    clients: Vec<Client>,
+}
+
+impl LiveSplitServer {
+    /// The port LiveSplit Server listens on by default.
+    pub const DEFAULT_PORT: u16 = 16834;
+    /// Start listening on `port` on localhost. Port 0 picks any free port.
+    ///
+    /// # Errors
+    ///
+    /// * If the port is already in use or can't be bound.
+    pub fn bind(port: u16) -> Result<Self, Error> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(LiveSplitServer {
+            listener,
+            clients: vec![],
+        })
+    }
+    /// Get the address the server is listening on.
+    ///
+    /// # Errors
+    ///
+    /// * If the address of the socket cannot be found.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+    /// Accept new clients, answer their queries and collect the requests from their commands.
+    pub fn poll<C: Clock>(&mut self, state: &RunState<C>) -> Vec<StateChangeRequest> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buf: vec![],
+                });
+            }
+        }
+        let mut requests = vec![];
+        self.clients.retain_mut(|client| {
+            let mut chunk = [0; 512];
+            loop {
+                match client.stream.read(&mut chunk) {
+                    Ok(0) => return false,
+                    Ok(n) => client.buf.extend_from_slice(&chunk[..n]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => return false,
+                }
+            }
+            while let Some(end) = client.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = client.buf.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line[..end]);
+                if let Some(answer) = handle(line.trim(), state, &mut requests) {
+                    if client
+                        .stream
+                        .write_all(format!("{}\r\n", answer).as_bytes())
+                        .is_err()
+                    {
+                        return false;
+                    }
+                }
+            }
+            true
+        });
+        requests
+    }
+}
+
+fn handle<C: Clock>(
+    line: &str,
+    state: &RunState<C>,
+    requests: &mut Vec<StateChangeRequest>,
+) -> Option<String> {
+    let (cmd, arg) = match line.split_once(' ') {
+        Some((c, a)) => (c, a.trim()),
+        None => (line, ""),
+    };
+    let phase = state.timer_state();
+    let running = matches!(phase, TimerState::Running | TimerState::Offset);
+    let request = match cmd {
+        "starttimer" if phase == TimerState::NotRunning => StateChangeRequest::Split,
+        "startorsplit" => StateChangeRequest::Split,
+        "split" if running => StateChangeRequest::Split,
+        "unsplit" => StateChangeRequest::Unsplit,
+        "skipsplit" => StateChangeRequest::Skip,
+        "pause" if running => StateChangeRequest::Pause,
+        "resume" if phase == TimerState::Paused => StateChangeRequest::Pause,
+        "reset" => StateChangeRequest::Reset,
+        "pausegametime" => StateChangeRequest::PauseGameTime,
+        "unpausegametime" => StateChangeRequest::ResumeGameTime,
+        "setgametime" => StateChangeRequest::SetGameTime(readable_to_ms(arg)?),
+        "setcomparison" => StateChangeRequest::SetComparison(match arg {
+            "Personal Best" => Comp::PersonalBest,
+            "Best Segments" => Comp::Golds,
+            "Average Segments" => Comp::Average,
+            "Median Segments" => Comp::Median,
+            "Balanced PB" => Comp::BalancedPb,
+            "Latest Run" => Comp::LatestRun,
+            "None" => Comp::None,
+            name => Comp::Custom(
+                state
+                    .run()
+                    .borrow()
+                    .custom_comparisons()
+                    .iter()
+                    .position(|c| c.name() == name)?,
+            ),
+        }),
+        "switchto" => {
+            let method = match arg {
+                "realtime" => TimingMethod::RealTime,
+                "gametime" => TimingMethod::GameTime,
+                _ => return None,
+            };
+            if method == state.method() {
+                return None;
+            }
+            StateChangeRequest::ToggleTimingMethod
+        }
+        _ => return query(cmd, state),
+    };
+    requests.push(request);
+    None
+}
+
+fn query<C: Clock>(cmd: &str, state: &RunState<C>) -> Option<String> {
+    let run = state.run().borrow();
+    let phase = state.timer_state();
+    let splits = run.splits();
+    // the splits that have been passed, including the last one once the run is finished
+    let passed = match phase {
+        TimerState::NotRunning => 0,
+        TimerState::Finished => splits.len(),
+        _ => state.current_split(),
+    };
+    let times = state.run_times();
+    let comp = state.comparison_times();
+    let time_or_dash = |t: u128| {
+        if t == 0 {
+            "-".to_owned()
+        } else {
+            ms_to_readable(t, None)
+        }
+    };
+    // the last split that was not skipped and its split time
+    let last = (0..passed)
+        .rev()
+        .find(|&i| times[i] != 0)
+        .map(|i| (i, times[..=i].iter().sum::<u128>()));
+    Some(match cmd {
+        "getcurrenttime" => match (phase, run.offset()) {
+            (TimerState::Offset, Some(off)) => {
+                format!(
+                    "-{}",
+                    ms_to_readable(off.saturating_sub(state.time()), None)
+                )
+            }
+            _ => ms_to_readable(state.time(), None),
+        },
+        "getlastsplittime" => last.map_or("-".to_owned(), |(_, t)| ms_to_readable(t, None)),
+        "getcomparisonsplittime" => time_or_dash(comp.get(state.current_split()).copied()?),
+        "getfinaltime" => time_or_dash(comp.last().copied()?),
+        "getdelta" => match last {
+            Some((i, t)) if comp[i] != 0 => diff_text(t as i128 - comp[i] as i128),
+            _ => "-".to_owned(),
+        },
+        "getsplitindex" => match phase {
+            TimerState::NotRunning => "-1".to_owned(),
+            _ => passed.to_string(),
+        },
+        "getcurrentsplitname" => match phase {
+            TimerState::NotRunning | TimerState::Finished => "-".to_owned(),
+            _ => splits[state.current_split()].clone(),
+        },
+        "getprevioussplitname" if passed > 0 => splits[passed - 1].clone(),
+        "getprevioussplitname" => "-".to_owned(),
+        "getcurrenttimerphase" => match phase {
+            TimerState::NotRunning => "NotRunning",
+            TimerState::Running | TimerState::Offset => "Running",
+            TimerState::Paused => "Paused",
+            TimerState::Finished => "Ended",
+        }
+        .to_owned(),
+        "ping" => "pong".to_owned(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::{FakeClock, Run};
+    use std::cell::RefCell;
+    use std::io::{BufRead, BufReader};
+    use std::rc::Rc;
+
+    fn send(
+        server: &mut LiveSplitServer,
+        client: &mut TcpStream,
+        state: &mut RunState<FakeClock>,
+        line: &str,
+    ) {
+        client
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .unwrap();
+        // the server doesn't block, so give the line a moment to arrive
+        for _ in 0..100 {
+            let requests = server.poll(state);
+            if !requests.is_empty() {
+                state.update(&requests);
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("no request for {}", line);
+    }
+
+    fn ask(
+        server: &mut LiveSplitServer,
+        reader: &mut BufReader<TcpStream>,
+        state: &RunState<FakeClock>,
+        line: &str,
+    ) -> String {
+        reader
+            .get_mut()
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .unwrap();
+        let mut answer = String::new();
+        while answer.is_empty() {
+            server.poll(state);
+            reader.read_line(&mut answer).ok();
+        }
+        answer.trim_end().to_owned()
+    }
+
+    #[test]
+    fn test_protocol() {
+        let run = Run::new(
+            "test",
+            "test",
+            None,
+            3000,
+            &vec!["one".to_owned(), "two".to_owned()],
+            &vec![1000, 2000],
+            &vec![900, 1800],
+            &vec![(0, 0), (0, 0)],
+        );
+        let clock = FakeClock::new();
+        let mut state = RunState::with_clock(Rc::new(RefCell::new(run)), clock.clone());
+        state.run().borrow_mut().set_game_pb_times(&vec![0, 0]);
+        state.run().borrow_mut().set_game_gold_times(&vec![0, 0]);
+        let mut server = LiveSplitServer::bind(0).unwrap();
+        let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        reader
+            .get_ref()
+            .set_read_timeout(Some(std::time::Duration::from_millis(10)))
+            .unwrap();
+
+        assert_eq!(ask(&mut server, &mut reader, &state, "ping"), "pong");
+        assert_eq!(ask(&mut server, &mut reader, &state, "getsplitindex"), "-1");
+        send(&mut server, &mut client, &mut state, "starttimer");
+        clock.advance(1200);
+        send(&mut server, &mut client, &mut state, "split");
+        assert_eq!(ask(&mut server, &mut reader, &state, "getsplitindex"), "1");
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getcurrentsplitname"),
+            "two"
+        );
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getprevioussplitname"),
+            "one"
+        );
+        assert_eq!(ask(&mut server, &mut reader, &state, "getdelta"), "+0.2");
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getlastsplittime"),
+            "1.200"
+        );
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getcurrenttimerphase"),
+            "Running"
+        );
+        send(&mut server, &mut client, &mut state, "pause");
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getcurrenttimerphase"),
+            "Paused"
+        );
+        send(
+            &mut server,
+            &mut client,
+            &mut state,
+            "setcomparison Best Segments",
+        );
+        assert_eq!(state.comparison(), Comp::Golds);
+        assert_eq!(
+            ask(&mut server, &mut reader, &state, "getfinaltime"),
+            "2.700"
+        );
+    }
+}
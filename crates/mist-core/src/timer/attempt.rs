@@ -0,0 +1,115 @@
+use super::TimingMethod;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+/// How an attempt of a run came to an end.
+pub enum AttemptEnd {
+    /// The runner completed the last split.
+    Finished,
+    /// The timer was reset before the run was completed.
+    Reset,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// A single attempt of a run, recorded when it is either finished or reset.
+pub struct Attempt {
+    started: u128,
+    end: AttemptEnd,
+    segments: Vec<Option<u128>>,
+    pause_time: u128,
+    #[serde(default)]
+    time: u128,
+    #[serde(default)]
+    game_segments: Vec<Option<u128>>,
+    #[serde(default)]
+    game_time: u128,
+}
+
+impl Attempt {
+    /// Create a new attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `started` - when the attempt started, in milliseconds since the unix epoch.
+    /// * `end` - whether the attempt was finished or reset.
+    /// * `segments` - the time of each segment that was passed in the attempt. Skipped segments are `None`.
+    /// * `pause_time` - the total number of milliseconds spent paused during the attempt.
+    pub fn new(
+        started: u128,
+        end: AttemptEnd,
+        segments: &Vec<Option<u128>>,
+        pause_time: u128,
+    ) -> Self {
+        Attempt {
+            started,
+            end,
+            segments: segments.to_owned(),
+            pause_time,
+            time: 0,
+            game_segments: vec![],
+            game_time: 0,
+        }
+    }
+    /// Get the time the attempt was started, in milliseconds since the unix epoch.
+    pub fn started(&self) -> u128 {
+        self.started
+    }
+    /// Get how the attempt ended.
+    pub fn end(&self) -> AttemptEnd {
+        self.end
+    }
+    /// Get the segment times of the attempt.
+    ///
+    /// Contains one element for every split that was completed or skipped, so a reset attempt
+    /// has as many segments as the index of the split it was reset on. Skipped splits are `None`.
+    pub fn segments(&self) -> &Vec<Option<u128>> {
+        &self.segments
+    }
+    /// Get the total time spent paused during the attempt, in milliseconds.
+    pub fn pause_time(&self) -> u128 {
+        self.pause_time
+    }
+    /// Get the time on the timer when the attempt ended, in milliseconds.
+    ///
+    /// Attempts recorded before this time was kept only have their segments, so for them it is the sum of the
+    /// segments, with skipped segments counted as 0.
+    pub fn time(&self) -> u128 {
+        if self.time != 0 {
+            self.time
+        } else {
+            self.segments.iter().flatten().sum()
+        }
+    }
+    /// Get the game time segments of the attempt, like [`segments`](Attempt::segments). Empty if the attempt has no
+    /// game times.
+    pub fn game_segments(&self) -> &Vec<Option<u128>> {
+        &self.game_segments
+    }
+    /// Returns the segments for the given timing method.
+    pub fn segments_for(&self, method: TimingMethod) -> &Vec<Option<u128>> {
+        match method {
+            TimingMethod::RealTime => &self.segments,
+            TimingMethod::GameTime => &self.game_segments,
+        }
+    }
+    /// Get the game time when the attempt ended, like [`time`](Attempt::time). 0 if the attempt has no game times.
+    pub fn game_time(&self) -> u128 {
+        if self.game_time != 0 {
+            self.game_time
+        } else {
+            self.game_segments.iter().flatten().sum()
+        }
+    }
+    /// Set the time on the timer when the attempt ended.
+    pub fn set_time(&mut self, new: u128) {
+        self.time = new;
+    }
+    /// Set the game time segments of the attempt.
+    pub fn set_game_segments(&mut self, new: &Vec<Option<u128>>) {
+        self.game_segments = new.to_owned();
+    }
+    /// Set the game time when the attempt ended.
+    pub fn set_game_time(&mut self, new: u128) {
+        self.game_time = new;
+    }
+}
@@ -0,0 +1,61 @@
+use super::MistInstant;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of time for a [`RunState`](super::state::RunState).
+///
+/// mist uses [`MistInstant`], but anything that can say how much time has passed can drive a run,
+/// which makes it possible to run the timer without a real clock.
+pub trait Clock {
+    /// Get the number of milliseconds that have passed since the clock was created.
+    fn elapsed_ms(&self) -> u128;
+    /// Get the current time in milliseconds since the unix epoch. Used to timestamp attempts.
+    fn unix_ms(&self) -> u128;
+}
+
+impl Clock for MistInstant {
+    fn elapsed_ms(&self) -> u128 {
+        self.elapsed().as_millis()
+    }
+    fn unix_ms(&self) -> u128 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+/// A clock that only moves when it is told to.
+///
+/// Clones share the same time, so one can be given to a [`RunState`](super::state::RunState)
+/// and another kept around to advance it.
+pub struct FakeClock {
+    time: Rc<Cell<u128>>,
+}
+
+impl FakeClock {
+    /// Create a new FakeClock starting at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Move the clock forward by `ms` milliseconds.
+    pub fn advance(&self, ms: u128) {
+        self.time.set(self.time.get() + ms);
+    }
+    /// Set the clock to exactly `ms` milliseconds.
+    pub fn set(&self, ms: u128) {
+        self.time.set(ms);
+    }
+}
+
+impl Clock for FakeClock {
+    fn elapsed_ms(&self) -> u128 {
+        self.time.get()
+    }
+    // the fake clock starts at the unix epoch
+    fn unix_ms(&self) -> u128 {
+        self.time.get()
+    }
+}
@@ -1,21 +1,52 @@
-#[derive(PartialEq, Copy, Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+/// What the current attempt is compared against.
+///
+/// See [`analysis::comparison_segments`](crate::analysis::comparison_segments) for the times of each.
 pub enum Comparison {
     Average,
+    /// The median time of each segment over every recorded attempt.
+    Median,
+    /// Segments that are equally good compared to their history and add up to the personal best.
+    BalancedPb,
+    /// The segments of the most recent finished attempt.
+    LatestRun,
     PersonalBest,
     Golds,
+    /// The custom comparison of the run at the given index. See
+    /// [`Run::custom_comparisons`](super::Run::custom_comparisons).
+    Custom(usize),
     None,
 }
 
 impl Comparison {
-    pub fn next(&mut self) {
+    /// Switch to the next comparison. `custom` is the number of custom comparisons in the run, which come after the
+    /// golds.
+    pub fn next(&mut self, custom: usize) {
         match self {
             Comparison::Average => {
+                *self = Comparison::Median;
+            }
+            Comparison::Median => {
+                *self = Comparison::BalancedPb;
+            }
+            Comparison::BalancedPb => {
+                *self = Comparison::LatestRun;
+            }
+            Comparison::LatestRun => {
                 *self = Comparison::PersonalBest;
             }
             Comparison::PersonalBest => {
                 *self = Comparison::Golds;
             }
-            Comparison::Golds => {
+            Comparison::Golds if custom > 0 => {
+                *self = Comparison::Custom(0);
+            }
+            Comparison::Custom(i) if i.saturating_add(1) < custom => {
+                *self = Comparison::Custom(*i + 1);
+            }
+            Comparison::Golds | Comparison::Custom(_) => {
                 *self = Comparison::None;
             }
             Comparison::None => {
@@ -23,20 +54,139 @@ impl Comparison {
             }
         }
     }
-    pub fn prev(&mut self) {
+    /// Switch to the previous comparison. `custom` is the number of custom comparisons in the run.
+    pub fn prev(&mut self, custom: usize) {
         match self {
             Comparison::Average => {
                 *self = Comparison::None;
             }
-            Comparison::PersonalBest => {
+            Comparison::Median => {
                 *self = Comparison::Average;
             }
+            Comparison::BalancedPb => {
+                *self = Comparison::Median;
+            }
+            Comparison::LatestRun => {
+                *self = Comparison::BalancedPb;
+            }
+            Comparison::PersonalBest => {
+                *self = Comparison::LatestRun;
+            }
             Comparison::Golds => {
                 *self = Comparison::PersonalBest;
             }
+            // a comparison past the end of the list goes back to the last one
+            Comparison::Custom(i) => {
+                *self = match (*i).min(custom).checked_sub(1) {
+                    Some(prev) => Comparison::Custom(prev),
+                    None => Comparison::Golds,
+                };
+            }
+            Comparison::None if custom > 0 => {
+                *self = Comparison::Custom(custom - 1);
+            }
             Comparison::None => {
                 *self = Comparison::Golds;
             }
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// A comparison made by the runner, i.e. a goal or someone else's run, saved with the run.
+pub struct CustomComparison {
+    name: String,
+    times: Vec<u128>,
+}
+
+impl CustomComparison {
+    /// Create a new custom comparison.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name the comparison is shown and selected with.
+    /// * `times` - the time of the run at the end of each split. A time of 0 means the split has no time.
+    pub fn new<S>(name: S, times: &Vec<u128>) -> Self
+    where
+        S: ToString,
+    {
+        CustomComparison {
+            name: name.to_string(),
+            times: times.to_owned(),
+        }
+    }
+    /// Get the name of the comparison.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Get the time of the run at the end of each split.
+    pub fn times(&self) -> &Vec<u128> {
+        &self.times
+    }
+    /// Get the time of each segment, the difference between the time of its split and the last split before it that
+    /// has a time. Splits without a time have a segment of 0.
+    pub fn segments(&self) -> Vec<u128> {
+        let mut last = 0;
+        self.times
+            .iter()
+            .map(|&time| {
+                if time == 0 {
+                    return 0;
+                }
+                let segment = time.saturating_sub(last);
+                last = time;
+                segment
+            })
+            .collect()
+    }
+    /// Set the name of the comparison.
+    pub fn set_name<S>(&mut self, new: S)
+    where
+        S: ToString,
+    {
+        self.name = new.to_string();
+    }
+    /// Set the time of the run at the end of each split.
+    pub fn set_times(&mut self, new: &Vec<u128>) {
+        self.times = new.to_owned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle() {
+        let mut comp = Comparison::Golds;
+        comp.next(2);
+        assert_eq!(comp, Comparison::Custom(0));
+        comp.next(2);
+        assert_eq!(comp, Comparison::Custom(1));
+        comp.next(2);
+        assert_eq!(comp, Comparison::None);
+        comp.prev(2);
+        assert_eq!(comp, Comparison::Custom(1));
+        comp = Comparison::Golds;
+        comp.next(0);
+        assert_eq!(comp, Comparison::None);
+        comp.prev(0);
+        assert_eq!(comp, Comparison::Golds);
+        // comparisons the run doesn't have, i.e. after it was reloaded with fewer of them
+        comp = Comparison::Custom(1);
+        comp.prev(0);
+        assert_eq!(comp, Comparison::Golds);
+        comp = Comparison::Custom(5);
+        comp.prev(2);
+        assert_eq!(comp, Comparison::Custom(1));
+        comp = Comparison::Custom(usize::MAX);
+        comp.next(2);
+        assert_eq!(comp, Comparison::None);
+    }
+
+    #[test]
+    fn test_segments() {
+        let comp = CustomComparison::new("sub 1", &vec![1000, 0, 4000, 4500]);
+        assert_eq!(comp.segments(), [1000, 0, 3000, 500]);
+    }
+}
@@ -38,6 +38,29 @@ pub fn ms_to_readable(mut ms: u128, round: Option<u128>) -> String {
     return format!("0.{:03}", ms);
 }
 
+/// Read a time in the form [[H:]M:]S[.mmm], like the ones made by [`ms_to_readable`], into milliseconds.
+///
+/// Digits of the fraction past milliseconds are ignored. Returns `None` if the time can't be read or is too long.
+pub fn readable_to_ms(time: &str) -> Option<u128> {
+    let (whole, frac) = time.split_once('.').unwrap_or((time, ""));
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    // checked before slicing, since slicing in the middle of a multibyte character panics
+    if !frac.is_empty() && !digits(frac) {
+        return None;
+    }
+    if whole.split(':').count() > 3 {
+        return None;
+    }
+    let secs = whole.split(':').try_fold(0u128, |secs, part| {
+        if !digits(part) {
+            return None;
+        }
+        secs.checked_mul(60)?.checked_add(part.parse().ok()?)
+    })?;
+    let ms: u128 = format!("{:0<3}", &frac[..frac.len().min(3)]).parse().ok()?;
+    secs.checked_mul(1000)?.checked_add(ms)
+}
+
 /// Create the readable time for a time differences.
 ///
 /// Prefixes with `+` for lost time and `-` for gained time.
@@ -152,6 +175,18 @@ mod tests {
         assert_eq!(ms_to_readable(3_661_111, None), "1:01:01.111");
     }
     #[test]
+    fn test_readable_to_ms() {
+        assert_eq!(readable_to_ms("1:02:03.45"), Some(3_723_450));
+        assert_eq!(readable_to_ms("12.5"), Some(12_500));
+        assert_eq!(readable_to_ms("1:01:01.1119"), Some(3_661_111));
+        assert_eq!(readable_to_ms("bad"), None);
+        assert_eq!(readable_to_ms("1::2"), None);
+        assert_eq!(readable_to_ms("1:2:3:4"), None);
+        assert_eq!(readable_to_ms("1.ab€"), None);
+        assert_eq!(readable_to_ms("1.€"), None);
+        assert_eq!(readable_to_ms(&"9".repeat(60)), None);
+    }
+    #[test]
     fn test_rounding_30() {
         assert_eq!(round_ms(30, 500), 500);
         assert_eq!(round_ms(30, 710), 700);
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+/// The clock that times and comparisons are taken from.
+///
+/// `GameTime` runs alongside real time but can be paused on its own, i.e. to remove loads.
+/// Averages are only kept for real time.
+pub enum TimingMethod {
+    RealTime,
+    GameTime,
+}
+
+impl TimingMethod {
+    pub fn toggle(&mut self) {
+        match self {
+            TimingMethod::RealTime => {
+                *self = TimingMethod::GameTime;
+            }
+            TimingMethod::GameTime => {
+                *self = TimingMethod::RealTime;
+            }
+        }
+    }
+}
@@ -1,8 +1,14 @@
+mod attempt;
+mod clock;
 mod comparison;
 pub mod format;
 mod instant;
+mod method;
 mod run;
 pub mod state;
-pub use comparison::Comparison;
+pub use attempt::{Attempt, AttemptEnd};
+pub use clock::{Clock, FakeClock};
+pub use comparison::{Comparison, CustomComparison};
 pub use instant::MistInstant;
+pub use method::TimingMethod;
 pub use run::Run;
@@ -1,6 +1,7 @@
+use super::{Attempt, CustomComparison, TimingMethod};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 /// Holds information about a speedrun and a user's times.
 pub struct Run {
     game_title: String,
@@ -11,6 +12,21 @@ pub struct Run {
     pb_times: Vec<u128>,
     gold_times: Vec<u128>,
     sum_times: Vec<(u128, u128)>,
+    attempt_history: Vec<Attempt>,
+    #[serde(default)]
+    attempt_count: u128,
+    #[serde(default)]
+    finished_count: u128,
+    #[serde(default)]
+    reset_counts: Vec<u128>,
+    #[serde(default)]
+    game_pb: u128,
+    #[serde(default)]
+    game_pb_times: Vec<u128>,
+    #[serde(default)]
+    game_gold_times: Vec<u128>,
+    #[serde(default)]
+    custom_comparisons: Vec<CustomComparison>,
 }
 
 impl Run {
@@ -25,6 +41,14 @@ impl Run {
             pb_times: vec![],
             gold_times: vec![],
             sum_times: vec![],
+            attempt_history: vec![],
+            attempt_count: 0,
+            finished_count: 0,
+            reset_counts: vec![],
+            game_pb: 0,
+            game_pb_times: vec![],
+            game_gold_times: vec![],
+            custom_comparisons: vec![],
         }
     }
     pub fn new<S>(
@@ -49,6 +73,14 @@ impl Run {
             pb_times: pb_times.to_owned(),
             gold_times: gold_times.to_owned(),
             sum_times: sum_times.to_owned(),
+            attempt_history: vec![],
+            attempt_count: 0,
+            finished_count: 0,
+            reset_counts: vec![],
+            game_pb: 0,
+            game_pb_times: vec![],
+            game_gold_times: vec![],
+            custom_comparisons: vec![],
         }
     }
     /// Get the game title.
@@ -86,6 +118,59 @@ impl Run {
     pub fn sum_times(&self) -> &Vec<(u128, u128)> {
         &self.sum_times
     }
+    /// Returns every recorded attempt of the run, oldest first.
+    pub fn attempt_history(&self) -> &Vec<Attempt> {
+        &self.attempt_history
+    }
+    /// Get the number of times the run has been started.
+    pub fn attempt_count(&self) -> u128 {
+        self.attempt_count
+    }
+    /// Get the number of times the run has been completed.
+    pub fn finished_count(&self) -> u128 {
+        self.finished_count
+    }
+    /// Returns how many times the run has been reset on each split.
+    pub fn reset_counts(&self) -> &Vec<u128> {
+        &self.reset_counts
+    }
+    /// Get the game time pb of the run in ms.
+    pub fn game_pb(&self) -> u128 {
+        self.game_pb
+    }
+    /// Returns the game times that were set on each split on the last game time personal best.
+    pub fn game_pb_times(&self) -> &Vec<u128> {
+        &self.game_pb_times
+    }
+    /// Returns the best game time that the runner has achieved on each split.
+    pub fn game_gold_times(&self) -> &Vec<u128> {
+        &self.game_gold_times
+    }
+    /// Returns the comparisons made by the runner, in the order they are cycled through.
+    pub fn custom_comparisons(&self) -> &Vec<CustomComparison> {
+        &self.custom_comparisons
+    }
+    /// Get the pb of the run in ms for the given timing method.
+    pub fn pb_for(&self, method: TimingMethod) -> u128 {
+        match method {
+            TimingMethod::RealTime => self.pb,
+            TimingMethod::GameTime => self.game_pb,
+        }
+    }
+    /// Returns the pb times for the given timing method.
+    pub fn pb_times_for(&self, method: TimingMethod) -> &Vec<u128> {
+        match method {
+            TimingMethod::RealTime => &self.pb_times,
+            TimingMethod::GameTime => &self.game_pb_times,
+        }
+    }
+    /// Returns the gold times for the given timing method.
+    pub fn gold_times_for(&self, method: TimingMethod) -> &Vec<u128> {
+        match method {
+            TimingMethod::RealTime => &self.gold_times,
+            TimingMethod::GameTime => &self.game_gold_times,
+        }
+    }
     /// Sets the game title field.
     pub fn set_game_title<S>(&mut self, new: S)
     where
@@ -133,4 +218,52 @@ impl Run {
     pub fn set_sum_time(&mut self, new: (u128, u128), idx: usize) {
         self.sum_times[idx] = new
     }
+    /// Set the list of recorded attempts.
+    pub fn set_attempt_history(&mut self, new: &Vec<Attempt>) {
+        self.attempt_history = new.to_owned();
+    }
+    /// Add an attempt to the end of the attempt history.
+    pub fn add_attempt(&mut self, attempt: Attempt) {
+        self.attempt_history.push(attempt);
+    }
+    /// Set the number of times the run has been started.
+    pub fn set_attempt_count(&mut self, new: u128) {
+        self.attempt_count = new;
+    }
+    /// Set the number of times the run has been completed.
+    pub fn set_finished_count(&mut self, new: u128) {
+        self.finished_count = new;
+    }
+    /// Set the number of resets on every split.
+    pub fn set_reset_counts(&mut self, new: &Vec<u128>) {
+        self.reset_counts = new.to_owned();
+    }
+    /// Set the number of resets on one split, specified by `idx`.
+    pub fn set_reset_count(&mut self, new: u128, idx: usize) {
+        self.reset_counts[idx] = new;
+    }
+    /// Set the game time pb of the run.
+    pub fn set_game_pb(&mut self, new: u128) {
+        self.game_pb = new;
+    }
+    /// Set the game times for each split that were achieved on the current game time pb.
+    pub fn set_game_pb_times(&mut self, new: &Vec<u128>) {
+        self.game_pb_times = new.to_owned();
+    }
+    /// Set the best game time for each split.
+    pub fn set_game_gold_times(&mut self, new: &Vec<u128>) {
+        self.game_gold_times = new.to_owned();
+    }
+    /// Set a single game time gold, specified by `idx`
+    pub fn set_game_gold_time(&mut self, new: u128, idx: usize) {
+        self.game_gold_times[idx] = new;
+    }
+    /// Set the list of custom comparisons.
+    pub fn set_custom_comparisons(&mut self, new: &Vec<CustomComparison>) {
+        self.custom_comparisons = new.to_owned();
+    }
+    /// Add a custom comparison to the end of the list.
+    pub fn add_custom_comparison(&mut self, comparison: CustomComparison) {
+        self.custom_comparisons.push(comparison);
+    }
 }
@@ -1,32 +1,47 @@
 use super::format;
 use super::Comparison as Comp;
-use super::MistInstant;
 use super::Run;
+use super::{Attempt, AttemptEnd, TimingMethod};
+use super::{Clock, MistInstant};
+use crate::analysis;
+use serde::Serialize;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-pub struct RunState {
+pub struct RunState<C: Clock = MistInstant> {
     run: Rc<RefCell<Run>>,
-    timer: MistInstant,
+    timer: C,
     timer_state: TimerState,
     run_status: SplitStatus,
     comparison: Comp,
+    method: TimingMethod,
     run_times: Vec<u128>,
     run_diffs: Vec<i128>,
     run_golds: Vec<bool>,
+    game_run_times: Vec<u128>,
+    game_golds: Vec<bool>,
     sum_comp_times: Vec<u128>,
     before_pause: u128,
     before_pause_split: u128,
+    pause_start: u128,
+    pause_time: u128,
+    attempt_start: u128,
     split: u128,
     start: u128,
     time: u128,
+    game_time: u128,
+    game_split: u128,
+    game_paused: bool,
+    last_time: u128,
     current_split: usize,
     needs_save: bool,
     set_times: bool,
+    set_game_times: bool,
 }
 
-#[derive(PartialEq, Debug)]
-enum TimerState {
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// What the timer is currently doing.
+pub enum TimerState {
     Running,
     NotRunning,
     Paused,
@@ -43,20 +58,31 @@ pub enum StateChangeRequest {
     Skip,
     Reset,
     Comparison(bool),
+    SetComparison(Comp),
+    PauseGameTime,
+    ResumeGameTime,
+    SetGameTime(u128),
+    ToggleTimingMethod,
 }
 
-// commented items will be used for plugins later
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
 pub enum StateChange {
     None,
-    EnterOffset, /*{amt: u128}*/
+    EnterOffset {
+        amt: u128,
+    },
     ExitOffset,
     EnterSplit {
-        idx: usize, /*name: String, pb: u128, gold: u128 */
+        idx: usize,
+        name: String,
+        pb: u128,
+        gold: u128,
     },
     ExitSplit {
         idx: usize,
-        /*name: String,*/ status: SplitStatus,
+        name: String,
+        status: SplitStatus,
         time: u128,
         diff: i128,
     },
@@ -71,6 +97,11 @@ pub enum StateChange {
     ComparisonChanged {
         comp: Comp,
     },
+    GameTimePause,
+    GameTimeUnpause,
+    TimingMethodChanged {
+        method: TimingMethod,
+    },
 }
 
 pub struct RunUpdate {
@@ -81,7 +112,7 @@ pub struct RunUpdate {
     pub status: SplitStatus,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize)]
 pub enum SplitStatus {
     None,
     Ahead,
@@ -93,39 +124,87 @@ pub enum SplitStatus {
 
 impl RunState {
     pub fn new(run: Rc<RefCell<Run>>) -> Self {
+        Self::with_clock(run, MistInstant::now())
+    }
+}
+
+impl<C: Clock> RunState<C> {
+    /// Create a RunState that gets its time from `clock` instead of a [`MistInstant`].
+    pub fn with_clock(run: Rc<RefCell<Run>>, clock: C) -> Self {
         let sum_comp_times = format::split_time_sum(run.borrow().pb_times());
         let len = run.borrow().pb_times().len();
+        // runs made in code rather than read from a file can be missing these, and resets or game times would be lost
+        {
+            let mut run = run.borrow_mut();
+            let mut resets = run.reset_counts().to_owned();
+            let mut game_golds = run.game_gold_times().to_owned();
+            let mut game_times = run.game_pb_times().to_owned();
+            if resets.len() < len {
+                resets.resize(len, 0);
+                run.set_reset_counts(&resets);
+            }
+            if game_golds.len() < len {
+                game_golds.resize(len, 0);
+                run.set_game_gold_times(&game_golds);
+            }
+            if game_times.len() < len {
+                game_times.resize(len, 0);
+                run.set_game_pb_times(&game_times);
+            }
+        }
         Self {
             run,
-            timer: MistInstant::now(),
+            timer: clock,
             timer_state: TimerState::NotRunning,
             comparison: Comp::PersonalBest,
+            method: TimingMethod::RealTime,
             run_status: SplitStatus::None,
             run_times: vec![0; len],
             run_diffs: vec![0; len],
             run_golds: vec![false; len],
+            game_run_times: vec![0; len],
+            game_golds: vec![false; len],
             sum_comp_times,
             before_pause: 0,
             before_pause_split: 0,
+            pause_start: 0,
+            pause_time: 0,
+            attempt_start: 0,
             split: 0,
             start: 0,
             time: 0,
+            game_time: 0,
+            game_split: 0,
+            game_paused: false,
+            last_time: 0,
             current_split: 0,
             needs_save: false,
             set_times: false,
+            set_game_times: false,
         }
     }
     pub fn update(&mut self, rq: &[StateChangeRequest]) -> RunUpdate {
-        let elapsed = self.timer.elapsed().as_millis();
+        let elapsed = self.timer.elapsed_ms();
         if self.timer_state == TimerState::Running || self.timer_state == TimerState::Offset {
             self.time = (elapsed - self.start) + self.before_pause;
         }
+        // game time only moves forward as much as real time did since the last update, unless it is paused
+        if self.timer_state == TimerState::Running && !self.game_paused {
+            self.game_time += self.time.saturating_sub(self.last_time);
+        }
+        self.last_time = self.time;
 
         // have to set pb times here or else the renderer sees them too early...
         if self.set_times {
             self.run.borrow_mut().set_pb_times(&self.run_times);
             self.set_times = false;
         }
+        if self.set_game_times {
+            self.run
+                .borrow_mut()
+                .set_game_pb_times(&self.game_run_times);
+            self.set_game_times = false;
+        }
 
         let mut change = rq.iter().fold(Vec::new(), |mut vec, request| {
             vec.append(&mut self.handle_scrq(request, elapsed));
@@ -137,15 +216,25 @@ impl RunState {
             self.timer_state = TimerState::Running;
             self.start = elapsed;
             self.split = elapsed;
-            change.push(StateChange::EnterSplit { idx: 0 });
+            self.last_time = 0;
+            change.push(self.enter_split(0));
         }
 
         self.calc_status();
+        let offset = self.timer_state == TimerState::Offset;
         RunUpdate {
             change,
-            split_time: (elapsed - self.split) + self.before_pause_split,
-            time: self.time,
-            offset: self.timer_state == TimerState::Offset,
+            split_time: match self.method {
+                TimingMethod::RealTime => (elapsed - self.split) + self.before_pause_split,
+                TimingMethod::GameTime => self.game_time.saturating_sub(self.game_split),
+            },
+            // the offset counts down in real time no matter what
+            time: if offset {
+                self.time
+            } else {
+                self.method_time()
+            },
+            offset,
             status: self.run_status,
         }
     }
@@ -155,14 +244,87 @@ impl RunState {
     pub fn is_running(&self) -> bool {
         self.timer_state == TimerState::Running
     }
+    pub fn is_game_time_paused(&self) -> bool {
+        self.game_paused
+    }
+    pub fn timer_state(&self) -> TimerState {
+        self.timer_state
+    }
+    /// Get the run that is being timed.
+    pub fn run(&self) -> &Rc<RefCell<Run>> {
+        &self.run
+    }
+    /// Get the index of the split the runner is on.
+    pub fn current_split(&self) -> usize {
+        self.current_split
+    }
+    pub fn comparison(&self) -> Comp {
+        self.comparison
+    }
+    pub fn method(&self) -> TimingMethod {
+        self.method
+    }
+    /// Get the time of the run as of the last update, in the active timing method.
+    ///
+    /// While in the offset this counts up from 0 to the offset in real time.
+    pub fn time(&self) -> u128 {
+        if self.timer_state == TimerState::Offset {
+            self.time
+        } else {
+            self.method_time()
+        }
+    }
+    /// Get the segment times of the current attempt in the active timing method. Skipped and future splits are 0.
+    pub fn run_times(&self) -> &Vec<u128> {
+        match self.method {
+            TimingMethod::RealTime => &self.run_times,
+            TimingMethod::GameTime => &self.game_run_times,
+        }
+    }
+    /// Get the split times (i.e. the sum of all segments up to and including each one) of the current comparison.
+    pub fn comparison_times(&self) -> &Vec<u128> {
+        &self.sum_comp_times
+    }
+    fn enter_split(&self, idx: usize) -> StateChange {
+        let run = self.run.borrow();
+        StateChange::EnterSplit {
+            idx,
+            name: run.splits()[idx].clone(),
+            pb: run.pb_times_for(self.method)[idx],
+            gold: run.gold_times_for(self.method)[idx],
+        }
+    }
+    fn exit_split(&self, idx: usize, time: u128, diff: i128) -> StateChange {
+        StateChange::ExitSplit {
+            idx,
+            name: self.run.borrow().splits()[idx].clone(),
+            status: self.run_status,
+            time,
+            diff,
+        }
+    }
+    fn method_time(&self) -> u128 {
+        match self.method {
+            TimingMethod::RealTime => self.time,
+            TimingMethod::GameTime => self.game_time,
+        }
+    }
+    fn calc_comp_times(&mut self) {
+        let run = self.run.borrow();
+        self.sum_comp_times = format::split_time_sum(&analysis::comparison_segments(
+            &run,
+            self.method,
+            self.comparison,
+        ));
+    }
     fn calc_status(&mut self) {
         if self.comparison == Comp::None || self.timer_state != TimerState::Running {
             self.run_status = SplitStatus::None;
             return;
         }
         let run = self.run.borrow();
-        if run.pb_times().is_empty() {
-            if self.time < run.pb() {
+        if run.pb_times_for(self.method).is_empty() {
+            if self.method_time() < run.pb_for(self.method) {
                 self.run_status = SplitStatus::Ahead;
             } else {
                 self.run_status = SplitStatus::Behind;
@@ -179,7 +341,7 @@ impl RunState {
                 return;
             }
             let allowed = allowed - buffer;
-            let time = self.time as i128;
+            let time = self.method_time() as i128;
             // if the last split was ahead of comparison split
             if buffer < 0 {
                 // if the runner has spent more time than allowed they have to be behind
@@ -209,6 +371,39 @@ impl RunState {
             }
         }
     }
+    fn record_attempt(&mut self, end: AttemptEnd) {
+        // a finished run passed every split, a reset one only those before the current split
+        let passed = if end == AttemptEnd::Finished {
+            self.run_times.len()
+        } else {
+            self.current_split
+        };
+        let to_segments = |times: &[u128]| -> Vec<Option<u128>> {
+            times[..passed]
+                .iter()
+                .map(|&t| if t == 0 { None } else { Some(t) })
+                .collect()
+        };
+        let mut attempt = Attempt::new(
+            self.attempt_start,
+            end,
+            &to_segments(&self.run_times),
+            self.pause_time,
+        );
+        // skipped segments are not in the segment times, so the time the timer ended on is kept as well
+        attempt.set_time(self.time);
+        attempt.set_game_segments(&to_segments(&self.game_run_times));
+        attempt.set_game_time(self.game_time);
+        let mut run = self.run.borrow_mut();
+        if end == AttemptEnd::Finished {
+            let count = run.finished_count();
+            run.set_finished_count(count + 1);
+        } else if let Some(&count) = run.reset_counts().get(self.current_split) {
+            run.set_reset_count(count + 1, self.current_split);
+        }
+        run.add_attempt(attempt);
+        self.needs_save = true;
+    }
     fn handle_scrq(&mut self, rq: &StateChangeRequest, elapsed: u128) -> Vec<StateChange> {
         use StateChangeRequest::*;
         match rq {
@@ -219,10 +414,12 @@ impl RunState {
                 self.timer_state = TimerState::Paused;
                 self.before_pause = self.time;
                 self.before_pause_split += elapsed - self.split;
+                self.pause_start = elapsed;
                 return vec![StateChange::Pause];
             }
             Pause if self.timer_state == TimerState::Paused => {
                 self.timer_state = TimerState::Running;
+                self.pause_time += elapsed - self.pause_start;
                 self.start = elapsed;
                 self.split = elapsed;
                 return vec![StateChange::Unpause {
@@ -231,25 +428,27 @@ impl RunState {
             }
             Split if self.timer_state == TimerState::Running => {
                 let time = (elapsed - self.split) + self.before_pause_split;
+                let game_time = self.game_time.saturating_sub(self.game_split);
                 self.split = elapsed;
+                self.game_split = self.game_time;
                 self.before_pause_split = 0;
                 self.run_times[self.current_split] = time;
-                self.run_diffs[self.current_split] = if self.comparison == Comp::PersonalBest {
-                    time as i128 - self.run.borrow().pb_times()[self.current_split] as i128
-                } else if self.comparison == Comp::Golds {
-                    time as i128 - self.run.borrow().gold_times()[self.current_split] as i128
-                } else if self.comparison == Comp::Average {
-                    let sum = self.run.borrow().sum_times()[self.current_split];
-                    time as i128
-                        - (sum.1 / {
-                            if sum.0 == 0 {
-                                1
-                            } else {
-                                sum.0
-                            }
-                        }) as i128
-                } else {
+                self.game_run_times[self.current_split] = game_time;
+                // the time used for comparisons is from whichever timing method is active
+                let comp_time = match self.method {
+                    TimingMethod::RealTime => time,
+                    TimingMethod::GameTime => game_time,
+                };
+                self.run_diffs[self.current_split] = if self.comparison == Comp::None {
                     0
+                } else {
+                    let idx = self.current_split;
+                    let before = if idx == 0 {
+                        0
+                    } else {
+                        self.sum_comp_times[idx - 1]
+                    };
+                    comp_time as i128 - (self.sum_comp_times[idx] - before) as i128
                 };
                 let mut sum = self.run.borrow().sum_times()[self.current_split];
                 sum.0 += 1;
@@ -260,12 +459,27 @@ impl RunState {
                     || self.run.borrow().gold_times()[self.current_split] == 0
                 {
                     self.run_golds[self.current_split] = true;
+                }
+                if game_time < self.run.borrow().game_gold_times()[self.current_split]
+                    || self.run.borrow().game_gold_times()[self.current_split] == 0
+                {
+                    self.game_golds[self.current_split] = true;
+                }
+                let is_gold = match self.method {
+                    TimingMethod::RealTime => self.run_golds[self.current_split],
+                    TimingMethod::GameTime => self.game_golds[self.current_split],
+                };
+                if is_gold {
                     self.run_status = SplitStatus::Gold;
                 }
-                let sum = format::split_time_sum(&self.run_times)[self.current_split];
+                let run_times = match self.method {
+                    TimingMethod::RealTime => &self.run_times,
+                    TimingMethod::GameTime => &self.game_run_times,
+                };
+                let sum = format::split_time_sum(run_times)[self.current_split];
                 let diff = sum as i128
-                    - format::split_time_sum(self.run.borrow().pb_times())[self.current_split]
-                        as i128;
+                    - format::split_time_sum(self.run.borrow().pb_times_for(self.method))
+                        [self.current_split] as i128;
                 if self.current_split == self.run.borrow().pb_times().len() - 1 {
                     {
                         let mut run = self.run.borrow_mut();
@@ -278,33 +492,37 @@ impl RunState {
                         {
                             run.set_gold_time(self.run_times[idx], idx);
                         }
+                        for idx in self
+                            .game_golds
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, &i)| i)
+                            .map(|(idx, _)| idx)
+                        {
+                            run.set_game_gold_time(self.game_run_times[idx], idx);
+                        }
                     }
                     self.timer_state = TimerState::Finished;
+                    self.record_attempt(AttemptEnd::Finished);
                     if self.time < self.run.borrow().pb() || self.run.borrow().pb() == 0 {
                         self.set_times = true;
                         self.run.borrow_mut().set_pb(self.time);
                     }
+                    if self.game_time < self.run.borrow().game_pb()
+                        || self.run.borrow().game_pb() == 0
+                    {
+                        self.set_game_times = true;
+                        self.run.borrow_mut().set_game_pb(self.game_time);
+                    }
                     return vec![
-                        StateChange::ExitSplit {
-                            idx: self.current_split,
-                            status: self.run_status,
-                            time: self.run_times[self.current_split],
-                            diff,
-                        },
+                        self.exit_split(self.current_split, comp_time, diff),
                         StateChange::Finish,
                     ];
                 } else {
                     self.current_split += 1;
                     return vec![
-                        StateChange::ExitSplit {
-                            idx: self.current_split - 1,
-                            status: self.run_status,
-                            time: self.run_times[self.current_split - 1],
-                            diff,
-                        },
-                        StateChange::EnterSplit {
-                            idx: self.current_split,
-                        },
+                        self.exit_split(self.current_split - 1, comp_time, diff),
+                        self.enter_split(self.current_split),
                     ];
                 }
             }
@@ -312,26 +530,51 @@ impl RunState {
                 self.start = elapsed;
                 self.split = elapsed;
                 self.time = 0;
-                if self.run.borrow().offset().is_some() {
+                self.game_time = 0;
+                self.game_split = 0;
+                self.last_time = 0;
+                self.pause_time = 0;
+                // the history and pb may have changed since the last attempt
+                self.calc_comp_times();
+                {
+                    let mut run = self.run.borrow_mut();
+                    let count = run.attempt_count();
+                    run.set_attempt_count(count + 1);
+                }
+                self.needs_save = true;
+                self.attempt_start = self.timer.unix_ms();
+                if let Some(amt) = self.run.borrow().offset() {
                     self.timer_state = TimerState::Offset;
-                    return vec![StateChange::EnterOffset];
+                    return vec![StateChange::EnterOffset { amt }];
                 } else {
                     self.timer_state = TimerState::Running;
-                    return vec![StateChange::EnterSplit { idx: 0 }];
+                    return vec![self.enter_split(0)];
                 }
             }
             Unsplit if self.timer_state == TimerState::Running && self.current_split != 0 => {
                 self.current_split -= 1;
                 self.before_pause_split = 0;
                 self.split -= self.run_times[self.current_split];
+                self.game_split = self
+                    .game_split
+                    .saturating_sub(self.game_run_times[self.current_split]);
                 self.run_diffs[self.current_split] = 0;
                 self.run_times[self.current_split] = 0;
                 self.run_golds[self.current_split] = false;
-                return vec![StateChange::EnterSplit {
-                    idx: self.current_split,
-                }];
+                self.game_run_times[self.current_split] = 0;
+                self.game_golds[self.current_split] = false;
+                return vec![self.enter_split(self.current_split)];
             }
             Reset => {
+                if self.timer_state == TimerState::Paused {
+                    self.pause_time += elapsed - self.pause_start;
+                }
+                // finished attempts were already recorded when the last split happened
+                if self.timer_state != TimerState::NotRunning
+                    && self.timer_state != TimerState::Finished
+                {
+                    self.record_attempt(AttemptEnd::Reset);
+                }
                 self.before_pause = 0;
                 self.before_pause_split = 0;
                 self.split = 0;
@@ -340,6 +583,12 @@ impl RunState {
                 self.run_diffs = vec![0; len];
                 self.run_times = vec![0; len];
                 self.run_golds = vec![false; len];
+                self.game_run_times = vec![0; len];
+                self.game_golds = vec![false; len];
+                self.game_time = 0;
+                self.game_split = 0;
+                self.game_paused = false;
+                self.last_time = 0;
                 self.current_split = 0;
                 self.timer_state = TimerState::NotRunning;
                 return vec![StateChange::Reset {
@@ -349,69 +598,482 @@ impl RunState {
             Skip if self.timer_state == TimerState::Running => {
                 self.run_times[self.current_split] = 0;
                 self.run_diffs[self.current_split] = 0;
+                self.game_run_times[self.current_split] = 0;
                 self.split = elapsed;
+                self.game_split = self.game_time;
                 self.before_pause_split = 0;
                 if self.current_split == self.run.borrow().pb_times().len() - 1 {
                     self.timer_state = TimerState::Finished;
+                    self.record_attempt(AttemptEnd::Finished);
                     return vec![
-                        StateChange::ExitSplit {
-                            idx: self.current_split,
-                            status: self.run_status,
-                            time: 0,
-                            diff: 0,
-                        },
+                        self.exit_split(self.current_split, 0, 0),
                         StateChange::Finish,
                     ];
                 } else {
                     self.current_split += 1;
                     return vec![
-                        StateChange::ExitSplit {
-                            idx: self.current_split - 1,
-                            status: self.run_status,
-                            time: 0,
-                            diff: 0,
-                        },
-                        StateChange::EnterSplit {
-                            idx: self.current_split,
-                        },
+                        self.exit_split(self.current_split - 1, 0, 0),
+                        self.enter_split(self.current_split),
                     ];
                 }
             }
             Comparison(n) => {
+                let custom = self.run.borrow().custom_comparisons().len();
                 if *n {
-                    self.comparison.next();
+                    self.comparison.next(custom);
                 } else {
-                    self.comparison.prev();
+                    self.comparison.prev(custom);
                 }
-                match self.comparison {
-                    Comp::PersonalBest => {
-                        self.sum_comp_times = format::split_time_sum(self.run.borrow().pb_times());
-                    }
-                    Comp::Golds => {
-                        self.sum_comp_times =
-                            format::split_time_sum(self.run.borrow().gold_times());
-                    }
-                    Comp::Average => {
-                        self.sum_comp_times = format::split_time_sum(
-                            &self
-                                .run
-                                .borrow()
-                                .sum_times()
-                                .iter()
-                                .map(|&(n, t)| if n != 0 { t / n } else { t })
-                                .collect(),
-                        )
-                    }
-                    Comp::None => {
-                        self.sum_comp_times = vec![0; self.run.borrow().pb_times().len()];
+                self.calc_comp_times();
+                return vec![StateChange::ComparisonChanged {
+                    comp: self.comparison,
+                }];
+            }
+            SetComparison(comp) => {
+                // requests from ipc, plugins or from before a reload can name a custom comparison the run doesn't have
+                self.comparison = match *comp {
+                    Comp::Custom(i) if i >= self.run.borrow().custom_comparisons().len() => {
+                        Comp::PersonalBest
                     }
-                }
+                    comp => comp,
+                };
+                self.calc_comp_times();
                 return vec![StateChange::ComparisonChanged {
                     comp: self.comparison,
                 }];
             }
+            PauseGameTime if !self.game_paused => {
+                self.game_paused = true;
+                return vec![StateChange::GameTimePause];
+            }
+            ResumeGameTime if self.game_paused => {
+                self.game_paused = false;
+                return vec![StateChange::GameTimeUnpause];
+            }
+            SetGameTime(t)
+                if self.timer_state == TimerState::Running
+                    || self.timer_state == TimerState::Paused =>
+            {
+                self.game_time = *t;
+            }
+            ToggleTimingMethod => {
+                self.method.toggle();
+                self.calc_comp_times();
+                return vec![StateChange::TimingMethodChanged {
+                    method: self.method,
+                }];
+            }
             _ => {}
         }
         vec![StateChange::None]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::{CustomComparison, FakeClock};
+
+    fn state(offset: Option<u128>) -> (RunState<FakeClock>, FakeClock, Rc<RefCell<Run>>) {
+        let mut run = Run::new(
+            "test",
+            "test",
+            offset,
+            3000,
+            &vec!["one".to_owned(), "two".to_owned(), "three".to_owned()],
+            &vec![1000, 1000, 1000],
+            &vec![900, 900, 900],
+            &vec![(0, 0), (0, 0), (0, 0)],
+        );
+        run.set_reset_counts(&vec![0, 0, 0]);
+        run.set_game_pb_times(&vec![0, 0, 0]);
+        run.set_game_gold_times(&vec![0, 0, 0]);
+        let run = Rc::new(RefCell::new(run));
+        let clock = FakeClock::new();
+        let state = RunState::with_clock(Rc::clone(&run), clock.clone());
+        (state, clock, run)
+    }
+
+    fn split_after(state: &mut RunState<FakeClock>, clock: &FakeClock, ms: u128) -> RunUpdate {
+        clock.advance(ms);
+        state.update(&[StateChangeRequest::Split])
+    }
+
+    #[test]
+    fn test_split() {
+        let (mut state, clock, run) = state(None);
+        let update = split_after(&mut state, &clock, 0);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::EnterSplit { idx: 0, .. }]
+        ));
+        assert!(state.is_running());
+        assert_eq!(run.borrow().attempt_count(), 1);
+        let update = split_after(&mut state, &clock, 1100);
+        assert!(matches!(
+            update.change[..],
+            [
+                StateChange::ExitSplit {
+                    idx: 0,
+                    time: 1100,
+                    diff: 100,
+                    ..
+                },
+                StateChange::EnterSplit { idx: 1, .. }
+            ]
+        ));
+        assert_eq!(update.time, 1100);
+        clock.advance(250);
+        let update = state.update(&[]);
+        assert_eq!(update.time, 1350);
+        assert_eq!(update.split_time, 250);
+    }
+
+    #[test]
+    fn test_finish_sets_pb_and_golds() {
+        let (mut state, clock, run) = state(None);
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 800);
+        split_after(&mut state, &clock, 1000);
+        let update = split_after(&mut state, &clock, 950);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::ExitSplit { idx: 2, .. }, StateChange::Finish]
+        ));
+        assert!(!state.is_running());
+        assert_eq!(run.borrow().pb(), 2750);
+        assert_eq!(run.borrow().gold_times(), &vec![800, 900, 900]);
+        // pb times are only set on the update after the run finishes
+        assert_eq!(run.borrow().pb_times(), &vec![1000, 1000, 1000]);
+        state.update(&[]);
+        assert_eq!(run.borrow().pb_times(), &vec![800, 1000, 950]);
+        assert_eq!(run.borrow().finished_count(), 1);
+        assert_eq!(run.borrow().attempt_history().len(), 1);
+        assert_eq!(
+            run.borrow().attempt_history()[0].end(),
+            AttemptEnd::Finished
+        );
+        assert!(state.needs_save());
+    }
+
+    #[test]
+    fn test_slower_run_keeps_pb() {
+        let (mut state, clock, run) = state(None);
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 1000);
+        split_after(&mut state, &clock, 1000);
+        split_after(&mut state, &clock, 1500);
+        state.update(&[]);
+        assert_eq!(run.borrow().pb(), 3000);
+        assert_eq!(run.borrow().pb_times(), &vec![1000, 1000, 1000]);
+        assert_eq!(run.borrow().gold_times(), &vec![900, 900, 900]);
+    }
+
+    #[test]
+    fn test_skip() {
+        let (mut state, clock, run) = state(None);
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 1000);
+        clock.advance(500);
+        let update = state.update(&[StateChangeRequest::Skip]);
+        assert!(matches!(
+            update.change[..],
+            [
+                StateChange::ExitSplit {
+                    idx: 1,
+                    time: 0,
+                    ..
+                },
+                StateChange::EnterSplit { idx: 2, .. }
+            ]
+        ));
+        clock.advance(500);
+        let update = state.update(&[StateChangeRequest::Skip]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::ExitSplit { idx: 2, .. }, StateChange::Finish]
+        ));
+        let run = run.borrow();
+        assert_eq!(
+            run.attempt_history()[0].segments(),
+            &vec![Some(1000), None, None]
+        );
+        // skipped segments never count as golds
+        assert_eq!(run.gold_times(), &vec![900, 900, 900]);
+    }
+
+    #[test]
+    fn test_unsplit() {
+        let (mut state, clock, _) = state(None);
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 1000);
+        clock.advance(200);
+        let update = state.update(&[StateChangeRequest::Unsplit]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::EnterSplit { idx: 0, .. }]
+        ));
+        // the first split continues as if it had never been split
+        let update = split_after(&mut state, &clock, 300);
+        assert!(matches!(
+            update.change[0],
+            StateChange::ExitSplit {
+                idx: 0,
+                time: 1500,
+                ..
+            }
+        ));
+        // can't unsplit before the first split
+        state.update(&[StateChangeRequest::Unsplit]);
+        let update = state.update(&[StateChangeRequest::Unsplit]);
+        assert!(matches!(update.change[..], [StateChange::None]));
+    }
+
+    #[test]
+    fn test_pause() {
+        let (mut state, clock, run) = state(None);
+        split_after(&mut state, &clock, 0);
+        clock.advance(500);
+        let update = state.update(&[StateChangeRequest::Pause]);
+        assert!(matches!(update.change[..], [StateChange::Pause]));
+        clock.advance(1000);
+        assert_eq!(state.update(&[]).time, 500);
+        let update = state.update(&[StateChangeRequest::Pause]);
+        assert!(matches!(update.change[..], [StateChange::Unpause { .. }]));
+        let update = split_after(&mut state, &clock, 500);
+        assert_eq!(update.time, 1000);
+        assert!(matches!(
+            update.change[0],
+            StateChange::ExitSplit { time: 1000, .. }
+        ));
+        state.update(&[StateChangeRequest::Reset]);
+        let run = run.borrow();
+        let attempt = &run.attempt_history()[0];
+        assert_eq!(attempt.end(), AttemptEnd::Reset);
+        assert_eq!(attempt.pause_time(), 1000);
+        assert_eq!(attempt.segments(), &vec![Some(1000)]);
+        assert_eq!(run.reset_counts(), &vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn test_offset() {
+        let (mut state, clock, _) = state(Some(500));
+        let update = split_after(&mut state, &clock, 0);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::EnterOffset { amt: 500 }]
+        ));
+        assert!(!state.is_running());
+        clock.advance(499);
+        let update = state.update(&[]);
+        assert!(update.offset);
+        assert_eq!(update.time, 499);
+        clock.advance(1);
+        let update = state.update(&[]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::EnterSplit { idx: 0, .. }]
+        ));
+        assert!(state.is_running());
+        let update = split_after(&mut state, &clock, 1000);
+        assert!(matches!(
+            update.change[0],
+            StateChange::ExitSplit {
+                idx: 0,
+                time: 1000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_reset() {
+        let (mut state, clock, run) = state(None);
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 1000);
+        clock.advance(100);
+        let update = state.update(&[StateChangeRequest::Reset]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::Reset { offset: None }]
+        ));
+        assert!(!state.is_running());
+        // resetting when not running does not record another attempt
+        state.update(&[StateChangeRequest::Reset]);
+        assert_eq!(run.borrow().attempt_history().len(), 1);
+        assert_eq!(run.borrow().sum_times()[0], (1, 1000));
+        let update = split_after(&mut state, &clock, 0);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::EnterSplit { idx: 0, .. }]
+        ));
+        assert_eq!(run.borrow().attempt_count(), 2);
+    }
+
+    #[test]
+    fn test_game_time_pause() {
+        let (mut state, clock, _) = state(None);
+        split_after(&mut state, &clock, 0);
+        clock.advance(500);
+        let update = state.update(&[StateChangeRequest::PauseGameTime]);
+        assert!(matches!(update.change[..], [StateChange::GameTimePause]));
+        assert!(state.is_game_time_paused());
+        let update = state.update(&[StateChangeRequest::PauseGameTime]);
+        assert!(matches!(update.change[..], [StateChange::None]));
+        clock.advance(300);
+        state.update(&[]);
+        let update = state.update(&[StateChangeRequest::ResumeGameTime]);
+        assert!(matches!(update.change[..], [StateChange::GameTimeUnpause]));
+        let update = state.update(&[StateChangeRequest::ResumeGameTime]);
+        assert!(matches!(update.change[..], [StateChange::None]));
+        let update = state.update(&[StateChangeRequest::ToggleTimingMethod]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::TimingMethodChanged {
+                method: TimingMethod::GameTime
+            }]
+        ));
+        // the 300ms spent with game time paused don't count
+        assert_eq!(update.time, 500);
+        let update = split_after(&mut state, &clock, 200);
+        assert!(matches!(
+            update.change[0],
+            StateChange::ExitSplit {
+                idx: 0,
+                time: 700,
+                ..
+            }
+        ));
+        assert_eq!(state.run_times(), &vec![700, 0, 0]);
+        state.update(&[StateChangeRequest::ToggleTimingMethod]);
+        assert_eq!(state.method(), TimingMethod::RealTime);
+        assert_eq!(state.time(), 1000);
+        assert_eq!(state.run_times(), &vec![1000, 0, 0]);
+    }
+
+    #[test]
+    fn test_set_game_time() {
+        let (mut state, clock, _) = state(None);
+        state.update(&[StateChangeRequest::ToggleTimingMethod]);
+        // game time can't be set before the run starts
+        state.update(&[StateChangeRequest::SetGameTime(5000)]);
+        let update = split_after(&mut state, &clock, 0);
+        assert_eq!(update.time, 0);
+        clock.advance(100);
+        state.update(&[StateChangeRequest::SetGameTime(2000)]);
+        assert_eq!(state.time(), 2000);
+        // game time keeps counting from the time it was set to
+        clock.advance(100);
+        assert_eq!(state.update(&[]).time, 2100);
+        state.update(&[StateChangeRequest::Pause]);
+        clock.advance(100);
+        let update = state.update(&[StateChangeRequest::SetGameTime(3000)]);
+        assert_eq!(update.time, 3000);
+        state.update(&[StateChangeRequest::Pause]);
+        let update = split_after(&mut state, &clock, 0);
+        assert!(matches!(
+            update.change[0],
+            StateChange::ExitSplit {
+                idx: 0,
+                time: 3000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_finish_sets_game_pb_and_golds() {
+        let (mut state, clock, run) = state(None);
+        state.update(&[StateChangeRequest::ToggleTimingMethod]);
+        let game_run = |state: &mut RunState<FakeClock>, times: [u128; 3]| {
+            split_after(state, &clock, 0);
+            let mut update = None;
+            for time in times.iter() {
+                clock.advance(1000);
+                state.update(&[StateChangeRequest::SetGameTime(*time)]);
+                update = Some(split_after(state, &clock, 0));
+            }
+            update.unwrap()
+        };
+        let update = game_run(&mut state, [800, 1700, 2300]);
+        assert!(matches!(
+            update.change[..],
+            [
+                StateChange::ExitSplit {
+                    idx: 2,
+                    status: SplitStatus::Gold,
+                    time: 600,
+                    ..
+                },
+                StateChange::Finish
+            ]
+        ));
+        assert_eq!(run.borrow().game_pb(), 2300);
+        assert_eq!(run.borrow().game_gold_times(), &vec![800, 900, 600]);
+        // the real time of 3000 ties the pb, so only the game time pb is set
+        assert_eq!(run.borrow().pb(), 3000);
+        assert_eq!(run.borrow().gold_times(), &vec![900, 900, 900]);
+        assert_eq!(run.borrow().game_pb_times(), &vec![0, 0, 0]);
+        state.update(&[]);
+        assert_eq!(run.borrow().game_pb_times(), &vec![800, 900, 600]);
+        assert_eq!(run.borrow().pb_times(), &vec![1000, 1000, 1000]);
+        {
+            let run = run.borrow();
+            let attempt = &run.attempt_history()[0];
+            assert_eq!(
+                attempt.game_segments(),
+                &vec![Some(800), Some(900), Some(600)]
+            );
+            assert_eq!(attempt.game_time(), 2300);
+        }
+        // a slower run only sets the golds it beat
+        state.update(&[StateChangeRequest::Reset]);
+        game_run(&mut state, [700, 1700, 2400]);
+        state.update(&[]);
+        assert_eq!(run.borrow().game_pb(), 2300);
+        assert_eq!(run.borrow().game_pb_times(), &vec![800, 900, 600]);
+        assert_eq!(run.borrow().game_gold_times(), &vec![700, 900, 600]);
+    }
+
+    #[test]
+    fn test_reset_new_run() {
+        let run = Rc::new(RefCell::new(Run::new(
+            "test",
+            "test",
+            None,
+            0,
+            &vec!["one".to_owned(), "two".to_owned()],
+            &vec![0, 0],
+            &vec![0, 0],
+            &vec![(0, 0), (0, 0)],
+        )));
+        let clock = FakeClock::new();
+        let mut state = RunState::with_clock(Rc::clone(&run), clock.clone());
+        split_after(&mut state, &clock, 0);
+        split_after(&mut state, &clock, 1000);
+        state.update(&[StateChangeRequest::Reset]);
+        assert_eq!(run.borrow().reset_counts(), &vec![0, 1]);
+        assert_eq!(run.borrow().game_gold_times(), &vec![0, 0]);
+    }
+
+    #[test]
+    fn test_set_missing_custom_comparison() {
+        let (mut state, _, run) = state(None);
+        let update = state.update(&[StateChangeRequest::SetComparison(Comp::Custom(3))]);
+        assert!(matches!(
+            update.change[..],
+            [StateChange::ComparisonChanged {
+                comp: Comp::PersonalBest
+            }]
+        ));
+        run.borrow_mut()
+            .add_custom_comparison(CustomComparison::new("goal", &vec![800, 1600, 2400]));
+        state.update(&[StateChangeRequest::SetComparison(Comp::Custom(0))]);
+        assert_eq!(state.comparison(), Comp::Custom(0));
+        assert_eq!(state.comparison_times(), &vec![800, 1600, 2400]);
+        // the run was replaced with one that has fewer custom comparisons
+        run.borrow_mut().set_custom_comparisons(&vec![]);
+        state.update(&[StateChangeRequest::Comparison(false)]);
+        assert_eq!(state.comparison(), Comp::Golds);
+    }
+}
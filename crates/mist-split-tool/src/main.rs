@@ -1,14 +1,20 @@
 use fltk::{
     app, button, dialog, draw,
     enums::{Align, Color, Font, FrameType},
-    input,
+    group, input, menu,
     prelude::*,
     table,
     window::*,
 };
 use lazy_static::lazy_static;
-use mist_core::{parse::LssParser, parse::MsfParser, timing::ms_to_readable, Run};
-use regex::Regex;
+use mist_core::{
+    analysis,
+    parse::{LssParser, LssWriter, MsfParser},
+    timer::{
+        format::{ms_to_readable, readable_to_ms},
+        Comparison, CustomComparison, Run, TimingMethod,
+    },
+};
 use std::convert::TryInto;
 use std::sync::Mutex;
 use tinyfiledialogs as tfd;
@@ -16,9 +22,6 @@ use tinyfiledialogs as tfd;
 static HEADERS: [&'static str; 3] = ["Split Name", "Personal Best", "Gold"];
 
 lazy_static! {
-    static ref HOURS: Regex = Regex::new(r"^(\d+):([0-5]\d):([0-5]\d)(?:\.(\d{1,3}))?$").unwrap();
-    static ref MINS: Regex = Regex::new(r"^([0-5]?\d):([0-5]\d)(?:\.(\d{1,3}))?$").unwrap();
-    static ref SECS: Regex = Regex::new(r"^([0-5]?\d)(?:\.(\d{1,3}))?$").unwrap();
     static ref RUN: Mutex<Run> = Mutex::new(Run::empty());
     static ref VECS: Mutex<(Vec<u128>, Vec<u128>, Vec<String>)> =
         Mutex::new((vec![], vec![], vec![]));
@@ -49,37 +52,177 @@ fn get_save_as() -> Option<String> {
     }
 }
 
+fn get_export_path() -> Option<String> {
+    match tfd::save_file_dialog_with_filter(
+        "Export to LiveSplit file",
+        "",
+        &["*.lss"],
+        "LiveSplit split files",
+    ) {
+        Some(mut p) => {
+            if p.ends_with(".lss") {
+                Some(p)
+            } else {
+                p.push_str(".lss");
+                Some(p)
+            }
+        }
+        None => None,
+    }
+}
+
+// times that can't be read are left empty
 fn str_to_ms(tm: String) -> u128 {
-    let mut ms: u128 = 0;
-    if HOURS.is_match(&tm) {
-        let caps = HOURS.captures_iter(&tm).next().unwrap();
-        ms = (caps[1].parse::<u128>().unwrap() * 3600000)
-            + (caps[2].parse::<u128>().unwrap() * 60000)
-            + (caps[3].parse::<u128>().unwrap() * 1000)
-            + caps
-                .get(4)
-                .map_or("0", |m| m.as_str())
-                .parse::<u128>()
-                .unwrap();
-    } else if MINS.is_match(&tm) {
-        let caps = MINS.captures_iter(&tm).next().unwrap();
-        ms = (caps[1].parse::<u128>().unwrap() * 60000)
-            + (caps[2].parse::<u128>().unwrap() * 1000)
-            + caps
-                .get(3)
-                .map_or("0", |m| m.as_str())
-                .parse::<u128>()
-                .unwrap();
-    } else if SECS.is_match(&tm) {
-        let caps = SECS.captures_iter(&tm).next().unwrap();
-        ms = (caps[1].parse::<u128>().unwrap() * 1000)
-            + caps
-                .get(2)
-                .map_or("0", |m| m.as_str())
-                .parse::<u128>()
-                .unwrap();
+    readable_to_ms(&tm).unwrap_or(0)
+}
+
+// list the custom comparisons of the run, with an entry for making a new one at the end
+fn fill_comparisons(choice: &mut menu::Choice) {
+    choice.clear();
+    for comparison in RUN.lock().unwrap().custom_comparisons() {
+        choice.add_choice(comparison.name());
+    }
+    choice.add_choice("new comparison");
+}
+
+// put the name and times of the comparison at `idx` in the inputs, or clear them for a new comparison
+fn load_comparison(idx: usize, name: &mut input::Input, times: &mut [input::Input]) {
+    let run = RUN.lock().unwrap();
+    let comparison = run.custom_comparisons().get(idx);
+    name.set_value(comparison.map_or("", |c| c.name()));
+    for (i, inp) in times.iter_mut().enumerate() {
+        let time = comparison
+            .and_then(|c| c.times().get(i).copied())
+            .unwrap_or(0);
+        if time == 0 {
+            inp.set_value("");
+        } else {
+            inp.set_value(&ms_to_readable(time, None));
+        }
     }
-    return ms;
+}
+
+// ask for a goal time and fill the inputs with a comparison that finishes at it
+fn goal_comparison(name: &mut input::Input, times: &mut [input::Input]) {
+    let goal = match dialog::input_default("Goal time:", "") {
+        Some(g) => str_to_ms(g),
+        None => return,
+    };
+    if goal == 0 {
+        dialog::alert_default("invalid time entered");
+        return;
+    }
+    let basis = match dialog::choice_default(
+        "Spread the goal over the segments like the",
+        "personal best",
+        "best segments",
+        "average",
+    ) {
+        0 => Comparison::PersonalBest,
+        1 => Comparison::Golds,
+        _ => Comparison::Average,
+    };
+    // the table may have changes that aren't in the run until it is saved
+    let mut run = RUN.lock().unwrap().clone();
+    {
+        let vecs = VECS.lock().unwrap();
+        run.set_pb_times(&vecs.0);
+        run.set_gold_times(&vecs.1);
+    }
+    match analysis::goal_times(&run, TimingMethod::RealTime, goal, basis) {
+        Some(goal_times) => {
+            if name.value().is_empty() {
+                name.set_value(&format!("sub {}", ms_to_readable(goal, None)));
+            }
+            for (inp, &time) in times.iter_mut().zip(&goal_times) {
+                if time == 0 {
+                    inp.set_value("");
+                } else {
+                    inp.set_value(&ms_to_readable(time, None));
+                }
+            }
+        }
+        None => dialog::alert_default("there are no times to spread the goal over"),
+    }
+}
+
+// window for adding, renaming, editing and deleting the custom comparisons of the run
+fn edit_comparisons() {
+    let splits = VECS.lock().unwrap().2.clone();
+    let mut win = Window::default()
+        .with_size(330, 400)
+        .center_screen()
+        .with_label("custom comparisons");
+    let mut choice = menu::Choice::new(100, 5, 180, 25, "Comparison: ");
+    let mut name_inp = input::Input::new(100, 35, 180, 25, "Name: ");
+    let scroll = group::Scroll::new(5, 65, 320, 300, "");
+    let mut time_inps: Vec<input::Input> = splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+            let mut inp = input::Input::new(150, 65 + 25 * i as i32, 150, 25, "");
+            inp.set_label(split);
+            inp
+        })
+        .collect();
+    scroll.end();
+    let mut save_button = button::Button::new(245, 370, 80, 25, "save");
+    let mut delete_button = button::Button::new(160, 370, 80, 25, "delete");
+    let mut goal_button = button::Button::new(75, 370, 80, 25, "from goal");
+    win.end();
+    win.make_modal(true);
+    win.show();
+    fill_comparisons(&mut choice);
+    choice.set_value(0);
+    load_comparison(0, &mut name_inp, &mut time_inps);
+    let mut name = name_inp.clone();
+    let mut times = time_inps.clone();
+    choice.set_callback(move |c| load_comparison(c.value() as usize, &mut name, &mut times));
+    let mut name = name_inp.clone();
+    let mut times = time_inps.clone();
+    goal_button.set_callback(move |_| goal_comparison(&mut name, &mut times));
+    let mut ch = choice.clone();
+    let name = name_inp.clone();
+    let times = time_inps.clone();
+    save_button.set_callback(move |_| {
+        if name.value().is_empty() {
+            dialog::alert_default("comparison needs a name");
+            return;
+        }
+        // times that can't be read count as no time, like in the split table
+        let comparison = CustomComparison::new(
+            name.value(),
+            &times.iter().map(|inp| str_to_ms(inp.value())).collect(),
+        );
+        let idx = ch.value() as usize;
+        {
+            let mut run = RUN.lock().unwrap();
+            let mut comparisons = run.custom_comparisons().to_owned();
+            if idx < comparisons.len() {
+                comparisons[idx] = comparison;
+            } else {
+                comparisons.push(comparison);
+            }
+            run.set_custom_comparisons(&comparisons);
+        }
+        fill_comparisons(&mut ch);
+        ch.set_value(idx as i32);
+    });
+    delete_button.set_callback(move |_| {
+        let idx = choice.value() as usize;
+        let len = {
+            let mut run = RUN.lock().unwrap();
+            let mut comparisons = run.custom_comparisons().to_owned();
+            if idx < comparisons.len() {
+                comparisons.remove(idx);
+            }
+            run.set_custom_comparisons(&comparisons);
+            comparisons.len()
+        };
+        fill_comparisons(&mut choice);
+        choice.set_value(len as i32);
+        load_comparison(len, &mut name_inp, &mut time_inps);
+    });
 }
 
 fn main() {
@@ -101,7 +244,7 @@ fn main() {
             } else {
                 let f = std::fs::File::open(p).unwrap();
                 let mut parser = LssParser::new(std::io::BufReader::new(f));
-                run = parser.parse();
+                run = parser.parse().unwrap();
                 *VECS.lock().unwrap() = (
                     run.pb_times().to_owned(),
                     run.gold_times().to_owned(),
@@ -132,6 +275,8 @@ fn main() {
     let mut add_button = button::Button::new(342, 60, 80, 25, "add split");
     let mut sub_button = button::Button::new(261, 60, 80, 25, "remove split");
     let mut open_button = button::Button::new(180, 60, 80, 25, "open file");
+    let mut export_button = button::Button::new(99, 60, 80, 25, "export lss");
+    let mut comp_button = button::Button::new(18, 60, 80, 25, "comparisons");
     let mut title_inp = input::Input::new(100, 5, 180, 25, "Category Title: ");
     let mut cat_inp = input::Input::new(100, 30, 180, 25, "Game Title: ");
     win.make_resizable(false);
@@ -159,7 +304,7 @@ fn main() {
                 } else {
                     let f = std::fs::File::open(p).unwrap();
                     let mut parser = LssParser::new(std::io::BufReader::new(f));
-                    let run = parser.parse();
+                    let run = parser.parse().unwrap();
                     *VECS.lock().unwrap() = (
                         run.pb_times().to_owned(),
                         run.gold_times().to_owned(),
@@ -213,6 +358,25 @@ fn main() {
             }
         }
     });
+    export_button.set_callback(move |_| {
+        let vecs = VECS.lock().unwrap();
+        let mut run = RUN.lock().unwrap();
+        run.set_pb_times(&vecs.0);
+        run.set_gold_times(&vecs.1);
+        run.set_splits(&vecs.2);
+        run.set_pb(vecs.0.iter().sum());
+        unsafe {
+            if !ILLEGAL {
+                if let Some(p) = get_export_path() {
+                    let f = std::fs::File::create(p).unwrap();
+                    LssWriter::new().write(&run, f).unwrap();
+                }
+            } else {
+                dialog::alert_default("invalid time(s) entered");
+            }
+        }
+    });
+    comp_button.set_callback(|_| edit_comparisons());
     table.draw_cell(move |t, ctx, row, col, x, y, w, h| match ctx {
         table::TableContext::StartPage => draw::set_font(Font::Helvetica, 14),
         table::TableContext::ColHeader => {
@@ -14,17 +14,28 @@ use sdl2::keyboard::Keycode;
 use sdl2::surface::Surface;
 
 use mist_core::{
-    config::Config,
+    analysis,
+    config::{paths, Config, Theme},
     dialogs,
     parse::MsfParser,
     timer::{
-        state::{RunState, RunUpdate, StateChangeRequest},
-        Run,
+        format::ms_to_readable,
+        state::{RunState, RunUpdate, StateChangeRequest, TimerState},
+        Comparison, CustomComparison, Run, TimingMethod,
     },
 };
 
+#[cfg(all(feature = "ipc", unix))]
+use mist_core::ipc::IpcServer;
+#[cfg(feature = "plugins")]
+use mist_core::plugin::PluginHost;
+#[cfg(feature = "server")]
+use mist_core::server::LiveSplitServer;
+
+use crate::cli::Args;
 use crate::keybinds::Keybinds;
 use crate::render::RenderState;
+use crate::watch::FileWatcher;
 
 pub struct App<'a, 'b> {
     _context: sdl2::Sdl,
@@ -34,11 +45,25 @@ pub struct App<'a, 'b> {
     config: Config,
     ev_pump: sdl2::EventPump,
     msf: MsfParser,
+    // split file from the command line or config, and command line options that override the config
+    file: Option<String>,
+    comparison: Option<Comparison>,
+    // goal time and the comparison it is spread over the segments like
+    goal: Option<(u128, Comparison)>,
+    // index of the goal comparison among the run's custom comparisons, which is left out when saving
+    goal_idx: Option<usize>,
+    inline_splits: Option<bool>,
+    #[cfg(all(feature = "ipc", unix))]
+    ipc: Option<IpcServer>,
+    #[cfg(feature = "server")]
+    server: Option<LiveSplitServer>,
+    #[cfg(feature = "plugins")]
+    plugins: PluginHost,
 }
 static ONE_SIXTIETH: Duration = Duration::new(0, 1_000_000_000 / 60);
 
 impl<'a, 'b> App<'a, 'b> {
-    pub fn init(context: sdl2::Sdl) -> Result<Self, String> {
+    pub fn init(context: sdl2::Sdl, args: Args) -> Result<Self, String> {
         let video = context.video()?;
         let mut window = video
             .window("mist", 300, 500)
@@ -48,23 +73,36 @@ impl<'a, 'b> App<'a, 'b> {
             .map_err(|_| get_error())?;
         #[cfg(feature = "icon")]
         {
-            let icon = Surface::from_file("assets/MIST.png")?;
+            let icon = Surface::from_file(paths::asset("MIST.png"))?;
             window.set_icon(icon);
         }
 
         let mut canvas = window.into_canvas().build().map_err(|_| get_error())?;
         let ev_pump = context.event_pump()?;
-        let mut config = Config::open()?;
+        let mut config = match &args.config {
+            Some(p) => Config::open_path(p),
+            None => Config::open(),
+        }
+        .map_err(|e| e.to_string())?;
+        for warning in config.warnings().iter().chain(config.theme().warnings()) {
+            eprintln!("{}: {}", config.path().display(), warning);
+        }
         let msf = MsfParser::new();
-        let run = Rc::new(RefCell::new(if let Some(x) = config.file() {
+        // a split file given on the command line is only used this time, so it isn't saved to the config
+        let mut file = args
+            .splits
+            .or_else(|| config.file().cloned())
+            .map(|f| paths::resolve(f).to_string_lossy().into_owned());
+        let run = Rc::new(RefCell::new(if let Some(x) = &file {
             let f = File::open(x).map_err(|e| e.to_string())?;
             let reader = BufReader::new(f);
-            msf.parse(reader)?
+            msf.parse(reader).map_err(|e| e.to_string())?
         } else {
             match dialogs::open_run() {
                 Ok(ret) => {
                     if let Some((r, path)) = ret {
                         config.set_file(&path);
+                        file = Some(path);
                         r
                     } else {
                         Run::empty()
@@ -82,38 +120,73 @@ impl<'a, 'b> App<'a, 'b> {
                 run.borrow().category(),
             ))
             .map_err(|_| get_error())?;
+        #[cfg(feature = "server")]
+        let server = config.server_port().and_then(|port| {
+            LiveSplitServer::bind(port)
+                .map_err(|e| eprintln!("could not start server on port {}: {}", port, e))
+                .ok()
+        });
+        let goal_basis = args.goal_basis.unwrap_or(Comparison::PersonalBest);
+        let goal = args.goal.map(|goal| (goal, goal_basis));
+        let mut ren_state = RenderState::new(Rc::clone(&run), canvas, &config)?;
+        if let Some(inline) = args.inline_splits {
+            ren_state.set_inline(inline)?;
+        }
         let app = App {
             _context: context,
-            ren_state: RenderState::new(Rc::clone(&run), canvas, &config)?,
+            ren_state,
             run_state: RunState::new(Rc::clone(&run)),
             config,
             ev_pump,
             msf,
             run,
+            file,
+            comparison: args.comparison,
+            goal,
+            goal_idx: None,
+            inline_splits: args.inline_splits,
+            // the timer still works without ipc, so a socket that can't be made isn't fatal
+            #[cfg(all(feature = "ipc", unix))]
+            ipc: IpcServer::bind(IpcServer::default_path())
+                .map_err(|e| eprintln!("could not start ipc server: {}", e))
+                .ok(),
+            #[cfg(feature = "server")]
+            server,
+            #[cfg(feature = "plugins")]
+            plugins: PluginHost::load_dirs(&paths::plugin_dirs()).map_err(|e| e.to_string())?,
         };
 
         Ok(app)
     }
 
     pub fn run(mut self) -> Result<(), String> {
-        let no_file: bool;
-        let mut path = if let Some(p) = self.config.file() {
-            no_file = false;
-            p.clone()
-        } else {
-            no_file = true;
-            "".to_owned()
-        };
+        let no_file = self.file.is_none();
+        let mut path = self.file.take().unwrap_or_default();
 
         // framerate cap timer
         let mut frame_time: Instant;
-        let mut binds = Keybinds::from_raw(self.config.binds())?;
-        let mut state_change_queue = vec![];
+        let mut binds = Keybinds::from_raw(self.config.binds()).map_err(|e| e.to_string())?;
+        self.goal_idx = add_goal(&self.run, self.goal);
+        if self.goal.is_some() && self.goal_idx.is_none() {
+            eprintln!("could not make a goal comparison: the goal basis has no times");
+        }
+        let mut state_change_queue: Vec<StateChangeRequest> = self
+            .comparison
+            .or_else(|| self.goal_idx.map(Comparison::Custom))
+            .map(StateChangeRequest::SetComparison)
+            .into_iter()
+            .collect();
         let mut update: RunUpdate;
+        // the config and split file are reloaded automatically when they are changed on disk
+        let mut config_watch = FileWatcher::new(self.config.path());
+        let mut splits_watch = FileWatcher::new(&path);
 
         // main loop
         'running: loop {
             frame_time = Instant::now();
+            // set when the config or theme changed, so that everything is drawn with the new one
+            let mut restyle = false;
+            let mut warnings: Vec<String> = vec![];
             // repeat stuff in here for every event that occured between frames
             // in order to properly respond to them
             for event in self.ev_pump.poll_iter() {
@@ -162,12 +235,22 @@ impl<'a, 'b> App<'a, 'b> {
                                             path = s;
                                             let mut f =
                                                 File::create(&path).map_err(|e| e.to_string())?;
-                                            self.msf.write(&self.run.borrow(), &mut f)?;
+                                            self.msf
+                                                .write(
+                                                    &saved_run(&self.run.borrow(), self.goal_idx),
+                                                    &mut f,
+                                                )
+                                                .map_err(|e| e.to_string())?;
                                         }
                                     } else {
                                         let mut f =
                                             File::create(&path).map_err(|e| e.to_string())?;
-                                        self.msf.write(&self.run.borrow(), &mut f)?;
+                                        self.msf
+                                            .write(
+                                                &saved_run(&self.run.borrow(), self.goal_idx),
+                                                &mut f,
+                                            )
+                                            .map_err(|e| e.to_string())?;
                                     }
                                 }
                                 // open a file dialog to get a new split file + run
@@ -176,28 +259,81 @@ impl<'a, 'b> App<'a, 'b> {
                                     Ok(s) => {
                                         if let Some((run, p)) = s {
                                             self.run.replace(run);
+                                            self.goal_idx = add_goal(&self.run, self.goal);
                                             self.config.set_file(&path);
                                             path = p;
                                         }
                                     }
                                     Err(e) => return Err(e.to_string()),
                                 }
-                                self.run_state = RunState::new(Rc::clone(&self.run));
-                                self.ren_state.reload_run()?;
+                                restart_run_state(
+                                    &self.run,
+                                    &mut self.run_state,
+                                    &mut self.ren_state,
+                                )?;
+                                splits_watch.set_path(&path);
                             }
                         } else if k == binds.skip_split {
                             state_change_queue.push(StateChangeRequest::Skip);
+                        } else if k == binds.switch_timing {
+                            state_change_queue.push(StateChangeRequest::ToggleTimingMethod);
                         } else if k == binds.load_config {
                             match dialogs::open_config() {
                                 Ok(c) => {
-                                    if let Some(conf) = c {
+                                    if let Some(mut conf) = c {
+                                        // the new config replaces the current one, so it is saved where that one was
+                                        conf.set_path(self.config.path());
                                         self.config = conf;
-                                        self.ren_state =
-                                            self.ren_state.reload_config(&self.config)?;
-                                        binds = Keybinds::from_raw(self.config.binds())?;
+                                        restyle = true;
+                                        binds = Keybinds::from_raw(self.config.binds())
+                                            .map_err(|e| e.to_string())?;
+                                    }
+                                }
+                                Err(e) => return Err(e.to_string()),
+                            }
+                        } else if k == binds.next_theme {
+                            // go through the theme files in order, then back to the config's own style
+                            // themes that can't be used are skipped so they don't block the ones after them
+                            let names = Theme::list();
+                            let start = match self.config.theme_name() {
+                                None => 0,
+                                Some(cur) => names
+                                    .iter()
+                                    .position(|n| n == cur)
+                                    .map_or(names.len(), |i| i + 1),
+                            };
+                            let prev = self.config.theme_name().map(str::to_owned);
+                            let mut failed = vec![];
+                            let mut switched = false;
+                            for next in names[start..]
+                                .iter()
+                                .map(|n| Some(n.as_str()))
+                                .chain(std::iter::once(None))
+                            {
+                                let config = &mut self.config;
+                                match config
+                                    .set_theme(next)
+                                    .and_then(|_| check_fonts(config.theme()))
+                                {
+                                    Ok(()) => {
+                                        switched = true;
+                                        break;
                                     }
+                                    Err(e) => failed.push(format!(
+                                        "could not switch to theme {}: {}",
+                                        next.unwrap_or("from the config"),
+                                        e
+                                    )),
                                 }
-                                Err(e) => return Err(e),
+                            }
+                            if switched {
+                                restyle = true;
+                                warnings.append(&mut failed);
+                                warnings.extend_from_slice(self.config.theme().warnings());
+                            } else {
+                                // the previous theme loaded before, so it will again
+                                let _ = self.config.set_theme(prev.as_deref());
+                                self.ren_state.show_error(&failed.join("\n"))?;
                             }
                         }
                     }
@@ -211,8 +347,63 @@ impl<'a, 'b> App<'a, 'b> {
                     _ => {}
                 }
             }
+            // a config that can't be used is shown as an error and the current one is kept
+            if config_watch.poll() {
+                match self.reload_config() {
+                    Ok((conf, b)) => {
+                        self.config = conf;
+                        binds = b;
+                        restyle = true;
+                        warnings.extend_from_slice(self.config.warnings());
+                        warnings.extend_from_slice(self.config.theme().warnings());
+                    }
+                    Err(e) => self
+                        .ren_state
+                        .show_error(&format!("could not reload config: {}", e))?,
+                }
+            }
+            if restyle {
+                self.ren_state = self.ren_state.reload_config(&self.config)?;
+                if let Some(inline) = self.inline_splits {
+                    self.ren_state.set_inline(inline)?;
+                }
+                if !warnings.is_empty() {
+                    self.ren_state.show_error(&warnings.join("\n"))?;
+                }
+            }
+            // only reload splits between runs, and never over changes to the run that haven't been saved yet
+            if self.run_state.timer_state() == TimerState::NotRunning && splits_watch.poll() {
+                if self.run_state.needs_save() {
+                    self.ren_state.show_error(
+                        "split file changed on disk, but it was not reloaded because this session's attempts are not saved yet",
+                    )?;
+                } else {
+                    self.reload_splits(&path)?;
+                }
+            }
+            #[cfg(all(feature = "ipc", unix))]
+            if let Some(ipc) = &mut self.ipc {
+                state_change_queue.append(&mut ipc.poll());
+            }
+            #[cfg(feature = "server")]
+            if let Some(server) = &mut self.server {
+                state_change_queue.append(&mut server.poll(&self.run_state));
+            }
+            #[cfg(feature = "plugins")]
+            {
+                state_change_queue.append(&mut self.plugins.poll());
+                for e in self.plugins.take_errors() {
+                    eprintln!("{}", e);
+                }
+            }
             update = self.run_state.update(&state_change_queue[..]);
             state_change_queue.clear();
+            #[cfg(all(feature = "ipc", unix))]
+            if let Some(ipc) = &mut self.ipc {
+                ipc.broadcast(&update.change);
+            }
+            #[cfg(feature = "plugins")]
+            self.plugins.broadcast(&update.change);
             self.ren_state.update(update)?;
             self.ren_state.render()?;
             if Instant::now().duration_since(frame_time) <= ONE_SIXTIETH {
@@ -222,7 +413,9 @@ impl<'a, 'b> App<'a, 'b> {
                 );
             }
         }
-        self.config.save()?;
+        #[cfg(feature = "plugins")]
+        self.plugins.shutdown();
+        self.config.save().map_err(|e| e.to_string())?;
         // if splits were updated, prompt user to save the split file
         if (self.run_state.needs_save() || no_file) && dialogs::save_check() {
             if path.is_empty() {
@@ -230,13 +423,94 @@ impl<'a, 'b> App<'a, 'b> {
                 if let Some(s) = p {
                     path = s;
                     let mut f = File::create(&path).map_err(|e| e.to_string())?;
-                    self.msf.write(&self.run.borrow(), &mut f)?;
+                    self.msf
+                        .write(&saved_run(&self.run.borrow(), self.goal_idx), &mut f)
+                        .map_err(|e| e.to_string())?;
                 }
             } else {
                 let mut f = File::create(&path).map_err(|e| e.to_string())?;
-                self.msf.write(&self.run.borrow(), &mut f)?;
+                self.msf
+                    .write(&saved_run(&self.run.borrow(), self.goal_idx), &mut f)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    // read the config again after it changed on disk, checking that its fonts and keybinds can be used
+    fn reload_config(&self) -> Result<(Config, Keybinds), mist_core::Error> {
+        let conf = self.config.reload()?;
+        check_fonts(conf.theme())?;
+        let binds = Keybinds::from_raw(conf.binds())?;
+        Ok((conf, binds))
+    }
+
+    // read the split file again after it changed on disk
+    // a file that can't be parsed is shown as an error and the current run is kept
+    fn reload_splits(&mut self, path: &str) -> Result<(), String> {
+        let loaded = File::open(path)
+            .map_err(mist_core::Error::from)
+            .and_then(|f| self.msf.parse(BufReader::new(f)));
+        match loaded {
+            Ok(run) => {
+                self.run.replace(run);
+                self.goal_idx = add_goal(&self.run, self.goal);
+                restart_run_state(&self.run, &mut self.run_state, &mut self.ren_state)?;
+                self.ren_state.clear_error();
+            }
+            Err(e) => {
+                self.ren_state
+                    .show_error(&format!("could not reload {}: {}", path, e))?;
             }
         }
         Ok(())
     }
 }
+
+// start timing the run again after it was replaced, keeping the comparison and timing method
+fn restart_run_state(
+    run: &Rc<RefCell<Run>>,
+    run_state: &mut RunState,
+    ren_state: &mut RenderState,
+) -> Result<(), String> {
+    let comparison = run_state.comparison();
+    let method = run_state.method();
+    *run_state = RunState::new(Rc::clone(run));
+    let mut requests = vec![StateChangeRequest::SetComparison(comparison)];
+    if run_state.method() != method {
+        requests.push(StateChangeRequest::ToggleTimingMethod);
+    }
+    ren_state.reload_run()?;
+    // the new run might not have the comparison, so the renderer follows whatever the state ends up on
+    ren_state.update(run_state.update(&requests))
+}
+
+// make sure the fonts of a theme can be found before switching to it, since not having them is fatal
+fn check_fonts(theme: &Theme) -> Result<(), mist_core::Error> {
+    theme.tfont().get_path()?;
+    theme.sfont().get_path()?;
+    Ok(())
+}
+
+// add the comparison for the goal from the command line after the run's own comparisons
+// returns its index, or None if there is no goal or it couldn't be made
+fn add_goal(run: &RefCell<Run>, goal: Option<(u128, Comparison)>) -> Option<usize> {
+    let (goal, basis) = goal?;
+    let times = analysis::goal_times(&run.borrow(), TimingMethod::RealTime, goal, basis)?;
+    let mut run = run.borrow_mut();
+    let name = format!("Goal {}", ms_to_readable(goal, None));
+    run.add_custom_comparison(CustomComparison::new(name, &times));
+    Some(run.custom_comparisons().len() - 1)
+}
+
+// the run as it is written to the split file, without the goal comparison that only lasts until mist closes
+// a comparison from the file with the same name as the goal is kept
+fn saved_run(run: &Run, goal_idx: Option<usize>) -> Run {
+    let mut run = run.clone();
+    if let Some(idx) = goal_idx {
+        let mut comparisons = run.custom_comparisons().to_owned();
+        comparisons.remove(idx);
+        run.set_custom_comparisons(&comparisons);
+    }
+    run
+}
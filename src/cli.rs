@@ -0,0 +1,173 @@
+// parse the command line arguments mist is started with
+use mist_core::timer::{format::readable_to_ms, Comparison};
+
+pub const HELP: &str = "\
+mist: a minimal, improved speedrun timer
+
+USAGE:
+    mist [OPTIONS] [SPLITS]
+
+ARGS:
+    <SPLITS>    split file to open instead of the one in the config
+
+OPTIONS:
+    -c, --config <FILE>        config file to use instead of the default one
+        --comparison <COMP>    comparison to start with [pb, golds, average, median, balanced, latest, none]
+        --goal <TIME>          start with a comparison that finishes at TIME, i.e. 1:30:00
+        --goal-basis <COMP>    comparison the goal is spread over the segments like [default: pb]
+        --layout <LAYOUT>      how to lay out the splits [inline, two-row]
+    -h, --help                 print this message and exit
+    -V, --version              print the version and exit
+
+Options given here only last until mist closes and are not saved to the config.";
+
+/// Options given on the command line, which override the config for this session.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub splits: Option<String>,
+    pub config: Option<String>,
+    pub comparison: Option<Comparison>,
+    pub goal: Option<u128>,
+    pub goal_basis: Option<Comparison>,
+    pub inline_splits: Option<bool>,
+}
+
+/// What mist was asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Run(Args),
+    Help,
+    Version,
+}
+
+/// Parse the arguments after the program name.
+///
+/// Options that take a value accept it either as the next argument or after an `=`.
+/// Everything after `--` is treated as the split file.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Action, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    let mut only_files = false;
+    while let Some(arg) = args.next() {
+        if only_files || !arg.starts_with('-') || arg == "-" {
+            if parsed.splits.is_some() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            parsed.splits = Some(arg);
+            continue;
+        }
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n.to_owned(), Some(v.to_owned())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("'{}' needs a value", name))
+        };
+        match name.as_str() {
+            "--" => only_files = true,
+            "-h" | "--help" => return Ok(Action::Help),
+            "-V" | "--version" => return Ok(Action::Version),
+            "-c" | "--config" => parsed.config = Some(value()?),
+            "--comparison" => parsed.comparison = Some(parse_comparison(&value()?)?),
+            "--goal" => parsed.goal = Some(parse_goal(&value()?)?),
+            "--goal-basis" => parsed.goal_basis = Some(parse_comparison(&value()?)?),
+            "--layout" => parsed.inline_splits = Some(parse_layout(&value()?)?),
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+    }
+    Ok(Action::Run(parsed))
+}
+
+fn parse_comparison(s: &str) -> Result<Comparison, String> {
+    match s {
+        "pb" => Ok(Comparison::PersonalBest),
+        "golds" => Ok(Comparison::Golds),
+        "average" => Ok(Comparison::Average),
+        "median" => Ok(Comparison::Median),
+        "balanced" => Ok(Comparison::BalancedPb),
+        "latest" => Ok(Comparison::LatestRun),
+        "none" => Ok(Comparison::None),
+        _ => Err(format!(
+            "unknown comparison '{}' (expected pb, golds, average, median, balanced, latest or none)",
+            s
+        )),
+    }
+}
+
+fn parse_goal(s: &str) -> Result<u128, String> {
+    match readable_to_ms(s) {
+        Some(goal) if goal > 0 => Ok(goal),
+        _ => Err(format!(
+            "invalid goal '{}' (expected a time like 1:30:00)",
+            s
+        )),
+    }
+}
+
+fn parse_layout(s: &str) -> Result<bool, String> {
+    match s {
+        "inline" => Ok(true),
+        "two-row" => Ok(false),
+        _ => Err(format!(
+            "unknown layout '{}' (expected inline or two-row)",
+            s
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Result<Action, String> {
+        parse(s.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(args(""), Ok(Action::Run(Args::default())));
+        assert_eq!(
+            args("any%.msf --config=/tmp/mist.cfg --comparison golds --layout two-row"),
+            Ok(Action::Run(Args {
+                splits: Some("any%.msf".to_owned()),
+                config: Some("/tmp/mist.cfg".to_owned()),
+                comparison: Some(Comparison::Golds),
+                goal: None,
+                goal_basis: None,
+                inline_splits: Some(false),
+            }))
+        );
+        assert_eq!(
+            args("-- --weird.msf"),
+            Ok(Action::Run(Args {
+                splits: Some("--weird.msf".to_owned()),
+                ..Args::default()
+            }))
+        );
+        assert_eq!(
+            args("--goal 1:30:00 --goal-basis=golds"),
+            Ok(Action::Run(Args {
+                goal: Some(5_400_000),
+                goal_basis: Some(Comparison::Golds),
+                ..Args::default()
+            }))
+        );
+        assert_eq!(args("a.msf --help"), Ok(Action::Help));
+        assert_eq!(args("-V"), Ok(Action::Version));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(args("--comparison").is_err());
+        assert!(args("--comparison fastest").is_err());
+        assert!(args("--layout sideways").is_err());
+        assert!(args("--goal soon").is_err());
+        assert!(args("--goal 1.ab€").is_err());
+        assert!(args(&format!("--goal {}", "9".repeat(60))).is_err());
+        assert!(args("--fly").is_err());
+        assert!(args("a.msf b.msf").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use mist_core::config::KeybindsRaw;
+use mist_core::{config::KeybindsRaw, Error};
 use sdl2::keyboard::Keycode;
 
 #[derive(Debug)]
@@ -12,27 +12,28 @@ pub struct Keybinds {
     pub next_comp: Keycode,
     pub load_splits: Keycode,
     pub load_config: Keycode,
+    pub switch_timing: Keycode,
+    pub next_theme: Keycode,
+}
+
+fn key(name: &str, bind: &str) -> Result<Keycode, Error> {
+    Keycode::from_name(name).ok_or_else(|| Error::InvalidKeybind(bind.to_owned()))
 }
 
 impl Keybinds {
-    pub fn from_raw(raw: &KeybindsRaw) -> Result<Self, String> {
+    pub fn from_raw(raw: &KeybindsRaw) -> Result<Self, Error> {
         Ok(Keybinds {
-            pause: Keycode::from_name(&raw.pause).ok_or("Pause keybind could not be parsed.")?,
-            reset: Keycode::from_name(&raw.reset).ok_or("Reset keybind could not be parsed.")?,
-            start_split: Keycode::from_name(&raw.start_split)
-                .ok_or("start/split keybind could not be parsed.")?,
-            skip_split: Keycode::from_name(&raw.skip_split)
-                .ok_or("Skip split keybind could not be parsed")?,
-            un_split: Keycode::from_name(&raw.un_split)
-                .ok_or("Unsplit keybind could not be parsed")?,
-            prev_comp: Keycode::from_name(&raw.prev_comp)
-                .ok_or("Prev comparison keybind could not be parsed")?,
-            next_comp: Keycode::from_name(&raw.next_comp)
-                .ok_or("Next comparison keybind could not be parsed")?,
-            load_splits: Keycode::from_name(&raw.load_splits)
-                .ok_or("Load splits keybind could not be parsed")?,
-            load_config: Keycode::from_name(&raw.load_config)
-                .ok_or("Load config keybind could not be parsed")?,
+            pause: key(&raw.pause, "Pause")?,
+            reset: key(&raw.reset, "Reset")?,
+            start_split: key(&raw.start_split, "Start/split")?,
+            skip_split: key(&raw.skip_split, "Skip split")?,
+            un_split: key(&raw.un_split, "Unsplit")?,
+            prev_comp: key(&raw.prev_comp, "Prev comparison")?,
+            next_comp: key(&raw.next_comp, "Next comparison")?,
+            load_splits: key(&raw.load_splits, "Load splits")?,
+            load_config: key(&raw.load_config, "Load config")?,
+            switch_timing: key(&raw.switch_timing, "Switch timing method")?,
+            next_theme: key(&raw.next_theme, "Next theme")?,
         })
     }
 }
@@ -4,14 +4,32 @@
 #![windows_subsystem = "windows"]
 
 mod app;
+mod cli;
 mod keybinds;
 mod panels;
 mod render;
 mod splits;
+mod watch;
 use app::App;
+use cli::Action;
 use mist_core::dialogs::error;
 
 fn main() {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(Action::Run(args)) => args,
+        Ok(Action::Help) => {
+            println!("{}", cli::HELP);
+            return;
+        }
+        Ok(Action::Version) => {
+            println!("mist {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::HELP);
+            std::process::exit(2);
+        }
+    };
     std::panic::set_hook(Box::new(|info| {
         let out = info.to_string();
         println!("{}", out);
@@ -20,7 +38,7 @@ fn main() {
     let context = sdl2::init().unwrap_or_else(|err| {
         error(&err);
     });
-    let app = App::init(context).unwrap_or_else(|err| {
+    let app = App::init(context, args).unwrap_or_else(|err| {
         error(&err);
     });
     app.run().unwrap_or_else(|err| {
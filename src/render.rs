@@ -1,9 +1,10 @@
 // Functions for putting stuff into the correct places on the sdl buffer
 use crate::panels::RenderPanel;
 use crate::splits::Split;
-use mist_core::config::{Config, Panel};
+use mist_core::analysis;
+use mist_core::config::{self, Align, ColorScheme, Component, Config, Panel};
 use mist_core::timer::state::{RunUpdate, SplitStatus, StateChange};
-use mist_core::timer::{format, Comparison, Run};
+use mist_core::timer::{format, Comparison, Run, TimingMethod};
 use sdl2::get_error;
 #[cfg(feature = "bg")]
 use sdl2::gfx::rotozoom::RotozoomSurface;
@@ -13,46 +14,93 @@ use sdl2::pixels::Color;
 #[cfg(feature = "bg")]
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{Texture, TextureCreator, TextureQuery, WindowCanvas};
+use sdl2::render::{BlendMode, Texture, TextureCreator, TextureQuery, WindowCanvas};
 use sdl2::rwops::RWops;
 #[cfg(feature = "bg")]
 use sdl2::surface::Surface;
 use sdl2::ttf::{self, Font, Sdl2TtfContext};
 use sdl2::video::WindowContext;
 use std::cell::RefCell;
-use std::convert::TryInto;
 use std::rc::Rc;
 
 const ALL_CHARS: &str =
     "AaBbCcDdEeFfGgHhIiJjKkLlMmNnOoPpQqRrSsTtUuVvWwXxYyZz`1234567890[]~!@#$%^&*(){}',./=\\-;\"<>?+|_:";
 const TIMER_CHARS: &str = "1234567890:.-";
+const SEPARATOR_HEIGHT: u32 = 3;
 
 pub struct RenderState<'a, 'b> {
     run: Rc<RefCell<Run>>,
     canvas: WindowCanvas,
     creator: TextureCreator<WindowContext>,
-    colors: [(u8, u8, u8); 6],
+    colors: ColorScheme,
     splits: Vec<Split>,
     panels: Vec<RenderPanel>,
-    map: FontMap,
+    timers: Vec<TimerDisplay<'a, 'b>>,
+    titles: Vec<TitleDisplay<'a, 'b>>,
+    segments: Vec<SegmentDisplay<'a, 'b>>,
+    layout: Vec<Element>,
     time_str: String,
+    segment_str: String,
     time_rounding: Option<u128>,
     is_running: bool,
-    timer_font: Font<'b, 'a>,
-    timer_height: u32,
+    comparison: Comparison,
+    method: TimingMethod,
     splits_font: Font<'b, 'a>,
     splits_height: u32,
     top_index: usize,
     bottom_index: usize,
     highlighted: usize,
     current: usize,
+    // the current split's pb and best segments from when it was entered
+    split_pb: u128,
+    split_gold: u128,
     max_splits: usize,
     inline: bool,
     status: SplitStatus,
+    // message about a file that failed to reload, drawn over the splits
+    error: Option<Texture>,
     #[cfg(feature = "bg")]
     background: Background,
 }
 
+// a component of the layout, pointing to the textures it draws
+#[derive(Clone, Copy)]
+enum Element {
+    Title(usize),
+    Splits { visible: Option<usize> },
+    Timer(usize),
+    SegmentTimer(usize),
+    Panel(usize),
+    Separator,
+    Spacer(u32),
+}
+
+struct TimerDisplay<'a, 'b> {
+    font: Font<'b, 'a>,
+    map: FontMap,
+    height: u32,
+    align: Align,
+}
+
+struct SegmentDisplay<'a, 'b> {
+    timer: TimerDisplay<'a, 'b>,
+    pb: bool,
+    best: bool,
+    // the split's pb and best times, drawn on the other side from the timer
+    times: Vec<Texture>,
+}
+
+struct TitleDisplay<'a, 'b> {
+    font: Font<'b, 'a>,
+    // the height of one line
+    height: u32,
+    game: Option<Texture>,
+    category: Option<Texture>,
+    count: Option<Texture>,
+    attempts: bool,
+    align: Align,
+}
+
 #[cfg(feature = "bg")]
 enum Background {
     NoBackground,
@@ -76,38 +124,90 @@ impl<'a, 'b> RenderState<'a, 'b> {
         config: &Config,
     ) -> Result<Self, String> {
         canvas.clear();
+        // so that colors with transparency are blended with what is behind them
+        canvas.set_blend_mode(BlendMode::Blend);
+        let theme = config.theme();
+        let colors = theme.colors();
         let creator = canvas.texture_creator();
-        let rw = RWops::from_file(config.tfont().get_path()?, "r")?;
-        let timer_font = TTF.load_font_from_rwops(rw, config.fsize().0)?;
-        let rw = RWops::from_file(config.sfont().get_path()?, "r")?;
-        let splits_font = TTF.load_font_from_rwops(rw, config.fsize().1)?;
-        let panels = {
-            let mut ret = vec![];
-            for &panel in config.panels() {
-                let (text, paneltype) = match panel {
-                    p @ Panel::Pace { golds } => {
-                        let text = if golds { "Pace (best)" } else { "Pace (pb)" };
-                        (text, p)
-                    }
-                    p @ Panel::SumOfBest => ("Sum of Best", p),
-                    p @ Panel::CurrentSplitDiff { golds } => {
-                        let text = if golds { "Split (best)" } else { "Split (pb)" };
-                        (text, p)
-                    }
-                };
-                let time = if let Panel::SumOfBest = panel {
-                    let sob = run.borrow().gold_times().iter().sum::<u128>();
-                    format::split_time_text(sob)
-                } else {
-                    "-  ".into()
-                };
-                let time_tex = render_text(&time, &splits_font, &creator, Color::WHITE)?;
-                let text_tex = render_text(&text, &splits_font, &creator, Color::WHITE)?;
-                let newpanel = RenderPanel::new(text_tex, time_tex, paneltype);
-                ret.push(newpanel);
-            }
-            ret
-        };
+        let tfont_path = theme.tfont().get_path().map_err(|e| e.to_string())?;
+        let sfont_path = theme.sfont().get_path().map_err(|e| e.to_string())?;
+        let rw = RWops::from_file(&sfont_path, "r")?;
+        let splits_font = TTF.load_font_from_rwops(rw, theme.fsize().1)?;
+        let mut timers = vec![];
+        let mut titles = vec![];
+        let mut segments = vec![];
+        let mut panels = vec![];
+        let mut layout = vec![];
+        for component in theme.layout().components(config.panels()) {
+            layout.push(match component {
+                Component::Title {
+                    attempts,
+                    font_size,
+                    align,
+                } => {
+                    let rw = RWops::from_file(&sfont_path, "r")?;
+                    let font =
+                        TTF.load_font_from_rwops(rw, font_size.unwrap_or(theme.fsize().1))?;
+                    titles.push(TitleDisplay {
+                        height: font.size_of(ALL_CHARS).map_err(|_| get_error())?.1,
+                        font,
+                        game: None,
+                        category: None,
+                        count: None,
+                        attempts,
+                        align,
+                    });
+                    Element::Title(titles.len() - 1)
+                }
+                // every list of splits would show the same thing, so only the first is used
+                Component::Splits { .. }
+                    if layout.iter().any(|e| matches!(e, Element::Splits { .. })) =>
+                {
+                    continue
+                }
+                Component::Splits { visible } => Element::Splits { visible },
+                Component::Timer { font_size, align } => {
+                    let rw = RWops::from_file(&tfont_path, "r")?;
+                    let font =
+                        TTF.load_font_from_rwops(rw, font_size.unwrap_or(theme.fsize().0))?;
+                    timers.push(TimerDisplay {
+                        map: FontMap::generate(&font, &creator, sdl_color(colors.text))?,
+                        height: font.size_of(TIMER_CHARS).map_err(|_| get_error())?.1,
+                        font,
+                        align,
+                    });
+                    Element::Timer(timers.len() - 1)
+                }
+                Component::SegmentTimer {
+                    font_size,
+                    align,
+                    pb,
+                    best,
+                } => {
+                    let rw = RWops::from_file(&tfont_path, "r")?;
+                    let font =
+                        TTF.load_font_from_rwops(rw, font_size.unwrap_or(theme.fsize().0 / 2))?;
+                    segments.push(SegmentDisplay {
+                        timer: TimerDisplay {
+                            map: FontMap::generate(&font, &creator, sdl_color(colors.text))?,
+                            height: font.size_of(TIMER_CHARS).map_err(|_| get_error())?.1,
+                            font,
+                            align,
+                        },
+                        pb,
+                        best,
+                        times: vec![],
+                    });
+                    Element::SegmentTimer(segments.len() - 1)
+                }
+                Component::Panel(panel) => {
+                    panels.push(new_panel(panel, &run, &splits_font, &creator, colors)?);
+                    Element::Panel(panels.len() - 1)
+                }
+                Component::Separator => Element::Separator,
+                Component::Spacer(height) => Element::Spacer(height),
+            });
+        }
         let string_times: Vec<String> = format::split_time_sum(run.borrow().pb_times())
             .iter()
             .map(|&t| {
@@ -125,132 +225,142 @@ impl<'a, 'b> RenderState<'a, 'b> {
             .enumerate()
             .map(|(idx, name)| {
                 Split::new(
-                    render_text(name, &splits_font, &creator, Color::WHITE).unwrap(),
-                    render_text(&string_times[idx], &splits_font, &creator, Color::WHITE).unwrap(),
+                    render_text(name, &splits_font, &creator, sdl_color(colors.text)).unwrap(),
+                    render_text(
+                        &string_times[idx],
+                        &splits_font,
+                        &creator,
+                        sdl_color(colors.text),
+                    )
+                    .unwrap(),
                     None,
                     None,
                 )
             })
             .collect();
         let splits_height = splits_font.size_of(ALL_CHARS).map_err(|_| get_error())?.1;
-        let timer_height = timer_font.size_of(TIMER_CHARS).map_err(|_| get_error())?.1;
-        let bottom_index: usize;
-        let max_splits: usize;
-        let max_initial_splits = ((canvas.viewport().height() - timer_height)
-            / ((splits_height * (1 + !config.layout().inline_splits as u32)) + 5))
-            as usize;
-        if splits.is_empty() {
-            max_splits = 0;
-            bottom_index = 0;
-        } else if max_initial_splits > splits.len() {
-            bottom_index = splits.len() - 1;
-            max_splits = splits.len();
-        } else {
-            max_splits = max_initial_splits;
-            bottom_index = max_initial_splits - 1;
-        }
         let time_str = if let Some(n) = run.borrow().offset() {
             format!("-{}", format::ms_to_readable(n, None))
         } else {
             "0.000".into()
         };
-        canvas
-            .window_mut()
-            .set_minimum_size(
-                100,
-                timer_height + 20 + (splits_height * panels.len() as u32),
-            )
-            .map_err(|_| get_error())?;
-        canvas
-            .window_mut()
-            .set_size(300, 500 + (splits_height * panels.len() as u32))
-            .map_err(|_| get_error())?;
-        canvas.present();
-        Ok(Self {
+        let mut state = Self {
             run,
-            colors: config.color_list(),
+            colors,
             splits,
             panels,
-            map: FontMap::generate(&timer_font, &creator, Color::WHITE)?,
+            timers,
+            titles,
+            segments,
+            layout,
             time_str,
+            segment_str: "0.000".into(),
             time_rounding: config.rounding(),
             is_running: false,
-            timer_font,
-            timer_height,
+            comparison: Comparison::PersonalBest,
+            method: TimingMethod::RealTime,
             splits_font,
             splits_height,
             top_index: 0,
-            bottom_index,
+            bottom_index: 0,
             highlighted: usize::MAX,
             current: 0,
-            max_splits,
-            inline: config.layout().inline_splits,
+            split_pb: 0,
+            split_gold: 0,
+            max_splits: 0,
+            inline: theme.layout().inline_splits,
             status: SplitStatus::None,
+            error: None,
             #[cfg(feature = "bg")]
-            background: Background::load(config, canvas.viewport(), &creator)?,
+            background: Background::NoBackground,
             canvas,
             creator,
-        })
+        };
+        // make room for the splits only if the layout doesn't give them a fixed size
+        let fixed_height = state.fixed_height();
+        let height = if state
+            .layout
+            .iter()
+            .any(|e| state.element_height(e).is_none())
+        {
+            fixed_height + 430
+        } else {
+            fixed_height
+        };
+        state
+            .canvas
+            .window_mut()
+            .set_minimum_size(100, height.min(fixed_height + 20))
+            .map_err(|_| get_error())?;
+        state
+            .canvas
+            .window_mut()
+            .set_size(300, height)
+            .map_err(|_| get_error())?;
+        state.canvas.present();
+        state.fit_splits(height);
+        state.update_titles()?;
+        state.update_segments()?;
+        state.update_possible(0, 0)?;
+        #[cfg(feature = "bg")]
+        {
+            state.background = Background::load(config, state.canvas.viewport(), &state.creator)?;
+        }
+        Ok(state)
     }
 
     pub fn update(&mut self, update: RunUpdate) -> Result<(), String> {
         if update.status != self.status {
             self.status = update.status;
             let color = match self.status {
-                SplitStatus::None => (255, 255, 255),
-                SplitStatus::Ahead => self.colors[0],
-                SplitStatus::Behind => self.colors[1],
-                SplitStatus::Gaining => self.colors[2],
-                SplitStatus::Losing => self.colors[3],
-                SplitStatus::Gold => self.colors[4],
+                SplitStatus::None => self.colors.text,
+                SplitStatus::Ahead => self.colors.ahead,
+                SplitStatus::Behind => self.colors.behind,
+                SplitStatus::Gaining => self.colors.gaining,
+                SplitStatus::Losing => self.colors.losing,
+                SplitStatus::Gold => self.colors.gold,
             };
-            self.map = FontMap::generate(&self.timer_font, &self.creator, color.into()).unwrap();
+            for timer in &mut self.timers {
+                timer.map = FontMap::generate(&timer.font, &self.creator, sdl_color(color))?;
+            }
         }
         if self.status != SplitStatus::None {
+            let run = self.run.borrow();
             for panel in &mut self.panels {
-                match panel.panel_type() {
-                    &Panel::Pace { golds } if self.run.borrow().pb_times()[self.current] != 0 => {
-                        let r = self.run.borrow();
-                        let times = if golds { r.gold_times() } else { r.pb_times() };
-                        let pace = format::split_time_text(
-                            times[self.current + 1..].iter().sum::<u128>() + update.time,
-                        );
-                        panel.set_time(render_text(
-                            pace,
-                            &self.splits_font,
-                            &self.creator,
-                            Color::WHITE,
-                        )?);
+                let text = match *panel.panel_type() {
+                    Panel::BestPossibleTime => {
+                        format::split_time_text(analysis::best_possible_time(
+                            &run,
+                            self.method,
+                            self.current,
+                            update.time,
+                            update.split_time,
+                        ))
                     }
-                    &Panel::CurrentSplitDiff { golds }
-                        if self.splits.len() > 1
-                            && self.run.borrow().pb_times()[self.current] != 0 =>
-                    {
-                        let compare_time: u128 = if golds {
-                            self.run.borrow().gold_times()[self.current]
-                        } else {
-                            self.run.borrow().pb_times()[self.current]
-                        };
-                        let time = if !golds {
-                            if update.split_time < compare_time {
-                                format::diff_text(-((compare_time - update.split_time) as i128))
-                            } else {
-                                format::diff_text((update.split_time - compare_time) as i128)
-                            }
-                        } else if update.split_time < compare_time {
-                            format::diff_text(-((compare_time - update.split_time) as i128))
-                        } else {
-                            format::diff_text((update.split_time - compare_time) as i128)
-                        };
-                        panel.set_time(render_text(
-                            time,
-                            &self.splits_font,
-                            &self.creator,
-                            Color::WHITE,
-                        )?);
+                    Panel::Pace { golds } => {
+                        match analysis::pace(&run, self.method, self.current, update.time, golds) {
+                            Some(pace) => format::split_time_text(pace),
+                            None => continue,
+                        }
                     }
-                    _ => {}
-                }
+                    Panel::CurrentSplitDiff { golds } => match analysis::split_diff(
+                        &run,
+                        self.method,
+                        self.current,
+                        update.split_time,
+                        golds,
+                    ) {
+                        Some(diff) => format::diff_text(diff),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                panel.set_time(render_text(
+                    text,
+                    &self.splits_font,
+                    &self.creator,
+                    sdl_color(self.colors.panel_value),
+                )?);
             }
         }
         for change in update.change {
@@ -261,6 +371,7 @@ impl<'a, 'b> RenderState<'a, 'b> {
                     self.time_str = format::ms_to_readable(update.time, self.time_rounding);
                 }
                 StateChange::Finish { .. } => {
+                    self.update_titles()?;
                     self.is_running = false;
                     self.time_str = format::ms_to_readable(update.time, self.time_rounding);
                     self.highlighted = usize::MAX;
@@ -272,28 +383,37 @@ impl<'a, 'b> RenderState<'a, 'b> {
                     status, time, diff, ..
                 } => {
                     let color = match status {
-                        SplitStatus::None => (255, 255, 255),
-                        SplitStatus::Ahead => self.colors[0],
-                        SplitStatus::Behind => self.colors[1],
-                        SplitStatus::Gaining => self.colors[2],
-                        SplitStatus::Losing => self.colors[3],
+                        SplitStatus::None => self.colors.text,
+                        SplitStatus::Ahead => self.colors.ahead,
+                        SplitStatus::Behind => self.colors.behind,
+                        SplitStatus::Gaining => self.colors.gaining,
+                        SplitStatus::Losing => self.colors.losing,
                         SplitStatus::Gold => {
-                            for panel in &mut self.panels {
-                                if *panel.panel_type() == Panel::SumOfBest {
-                                    panel.set_time(render_text(
-                                        format::split_time_text(
-                                            self.run.borrow().gold_times().iter().sum::<u128>(),
-                                        ),
-                                        &self.splits_font,
-                                        &self.creator,
-                                        Color::WHITE,
-                                    )?);
-                                }
-                            }
-                            self.colors[4]
+                            self.update_sob()?;
+                            self.colors.gold
                         }
                     };
-                    let time_str = if self.run.borrow().pb_times()[self.current] == 0 {
+                    for panel in &mut self.panels {
+                        if let Panel::PreviousSegment { golds } = *panel.panel_type() {
+                            let compare = if golds {
+                                self.split_gold
+                            } else {
+                                self.split_pb
+                            };
+                            let text = match analysis::segment_diff(time, compare) {
+                                Some(diff) => format::diff_text(diff),
+                                None => "-  ".into(),
+                            };
+                            panel.set_time(render_text(
+                                text,
+                                &self.splits_font,
+                                &self.creator,
+                                sdl_color(color),
+                            )?);
+                        }
+                    }
+                    let time_str = if self.run.borrow().pb_times_for(self.method)[self.current] == 0
+                    {
                         "-  ".into()
                     } else {
                         format::diff_text(diff)
@@ -303,32 +423,39 @@ impl<'a, 'b> RenderState<'a, 'b> {
                             "-  ",
                             &self.splits_font,
                             &self.creator,
-                            Color::WHITE,
+                            sdl_color(self.colors.text),
                         )?));
                     } else {
                         self.splits[self.current].set_diff(Some(render_text(
                             &time_str,
                             &self.splits_font,
                             &self.creator,
-                            color.into(),
+                            sdl_color(color),
                         )?));
                         let time_str = format::split_time_text(update.time);
                         self.splits[self.current].set_cur(Some(render_text(
                             &time_str,
                             &self.splits_font,
                             &self.creator,
-                            Color::WHITE,
+                            sdl_color(self.colors.text),
                         )?));
                     }
                 }
-                StateChange::EnterSplit { idx } => {
+                StateChange::EnterSplit { idx, pb, gold, .. } => {
                     self.is_running = true;
+                    self.split_pb = pb;
+                    self.split_gold = gold;
+                    if idx == 0 {
+                        self.update_attempts()?;
+                    }
                     // if we just unsplitted, remove the old textures
                     if idx < self.current {
                         self.splits[idx].set_cur(None);
                         self.splits[idx].set_diff(None);
                     }
                     self.current = idx;
+                    self.update_segments()?;
+                    self.update_possible(update.time, update.split_time)?;
                     if self.current > self.bottom_index {
                         self.top_index += self.current - self.bottom_index;
                         self.bottom_index = self.current;
@@ -351,105 +478,47 @@ impl<'a, 'b> RenderState<'a, 'b> {
                     } else {
                         self.time_str = "0.000".into();
                     }
+                    self.segment_str = "0.000".into();
+                    self.update_segments()?;
                     for split in &mut self.splits {
                         split.set_cur(None);
                         split.set_diff(None);
                     }
                     for panel in &mut self.panels {
-                        if !matches!(panel.panel_type(), Panel::SumOfBest) {
+                        if !matches!(
+                            panel.panel_type(),
+                            Panel::SumOfBest
+                                | Panel::Attempts
+                                | Panel::BestPossibleTime
+                                | Panel::PossibleTimeSave { .. }
+                        ) {
                             panel.set_time(render_text(
                                 "-  ",
                                 &self.splits_font,
                                 &self.creator,
-                                Color::WHITE,
+                                sdl_color(self.colors.panel_value),
                             )?);
                         }
                     }
+                    self.update_possible(0, 0)?;
+                    // the attempt that just ended changes comparisons made from the history
+                    self.update_comparison()?;
                     self.is_running = false;
                 }
-                StateChange::ComparisonChanged { comp } => match comp {
-                    Comparison::None => {
-                        for split in &mut self.splits {
-                            split.set_comp(render_text(
-                                "-  ",
-                                &self.splits_font,
-                                &self.creator,
-                                Color::WHITE,
-                            )?);
-                        }
-                    }
-                    Comparison::Average => {
-                        let mut i = 0;
-                        let (attempts, mut times) = {
-                            let mut att = vec![];
-                            let mut tm = vec![];
-                            for sum in self.run.borrow().sum_times() {
-                                att.push(sum.0);
-                                tm.push(sum.1);
-                            }
-                            (att, tm)
-                        };
-                        while i < attempts.len() {
-                            times[i] /= {
-                                if attempts[i] == 0 {
-                                    1
-                                } else {
-                                    attempts[i]
-                                }
-                            };
-                            i += 1;
-                        }
-                        let split_times_raw: Vec<String> = format::split_time_sum(&times)
-                            .iter()
-                            .map(|&val| {
-                                if val == 0 {
-                                    "-  ".into()
-                                } else {
-                                    format::split_time_text(val)
-                                }
-                            })
-                            .collect();
-                        i = 0;
-                        while i < self.splits.len() {
-                            self.splits[i].set_comp(render_text(
-                                &split_times_raw[i],
-                                &self.splits_font,
-                                &self.creator,
-                                Color::WHITE,
-                            )?);
-                            i += 1;
-                        }
-                    }
-                    c => {
-                        let split_times = match c {
-                            Comparison::PersonalBest => self.run.borrow().pb_times().clone(),
-                            Comparison::Golds => self.run.borrow().gold_times().clone(),
-                            _ => unreachable!(),
-                        };
-                        let split_times_raw: Vec<String> = format::split_time_sum(&split_times)
-                            .iter()
-                            .map(|&val| {
-                                if val == 0 {
-                                    "-  ".into()
-                                } else {
-                                    format::split_time_text(val)
-                                }
-                            })
-                            .collect();
-                        let mut i = 0;
-                        while i < self.splits.len() {
-                            self.splits[i].set_comp(render_text(
-                                &split_times_raw[i],
-                                &self.splits_font,
-                                &self.creator,
-                                Color::WHITE,
-                            )?);
-                            i += 1;
-                        }
-                    }
-                },
-                StateChange::EnterOffset => {
+                StateChange::ComparisonChanged { comp } => {
+                    self.comparison = comp;
+                    self.update_comparison()?;
+                }
+                StateChange::TimingMethodChanged { method } => {
+                    self.method = method;
+                    self.update_comparison()?;
+                    self.update_sob()?;
+                    self.update_segments()?;
+                    self.update_possible(update.time, update.split_time)?;
+                }
+                StateChange::EnterOffset { .. } => {
                     self.is_running = true;
+                    self.update_attempts()?;
                 }
                 _ => {}
             }
@@ -462,6 +531,7 @@ impl<'a, 'b> RenderState<'a, 'b> {
                 );
             } else {
                 self.time_str = format::ms_to_readable(update.time, None);
+                self.segment_str = format::ms_to_readable(update.split_time, None);
             }
         }
         self.update_highlighted();
@@ -480,63 +550,42 @@ impl<'a, 'b> RenderState<'a, 'b> {
     }
 
     pub fn win_resize(&mut self, y: u32) {
-        let row_height = self.splits_height + 5 + (!self.inline as u32 * self.splits_height);
-        let all_rows_height = row_height * self.max_splits as u32;
-        let bottom_height = self.timer_height + (self.splits_height * self.panels.len() as u32);
-        if y - bottom_height > all_rows_height + row_height {
-            let diff = (((y - bottom_height) - all_rows_height) / row_height) as usize;
-            if self.max_splits + diff < self.splits.len() {
-                self.max_splits += diff;
-            } else {
-                self.max_splits = self.splits.len();
-            }
-            if self.top_index > diff {
-                self.top_index -= diff;
-            } else if self.top_index != 0 {
-                let bottom_change = diff - self.top_index;
-                self.top_index = 0;
-                if self.bottom_index + bottom_change < self.splits.len() - 1 {
-                    self.bottom_index += bottom_change;
-                } else {
-                    self.bottom_index = self.splits.len() - 1;
-                }
-            } else if self.splits.len() > 0 && self.bottom_index + diff < self.splits.len() - 1 {
-                self.bottom_index += diff;
-            } else if self.splits.len() > 0 {
-                self.bottom_index = self.splits.len() - 1;
-            } else {
-                self.bottom_index = 0;
-            }
-        } else if y - bottom_height < all_rows_height {
-            let diff = ((all_rows_height - (y - bottom_height)) / row_height) as usize + 1;
-            if self.max_splits > diff {
-                self.max_splits -= diff;
-            } else {
-                self.max_splits = 0;
-                self.top_index = 0;
-                self.bottom_index = 0;
-                self.update_highlighted();
-                return;
-            }
-            if self.bottom_index - diff > self.top_index {
-                self.bottom_index -= diff;
-            } else {
-                self.bottom_index = self.top_index;
-            }
-        }
-        self.update_highlighted();
+        self.fit_splits(y);
     }
 
     pub fn render(&mut self) -> Result<(), String> {
-        self.canvas.set_draw_color(self.colors[5]);
+        self.canvas
+            .set_draw_color(sdl_color(self.colors.background));
         self.canvas.clear();
 
         #[cfg(feature = "bg")]
         self.render_bg()?;
 
-        self.render_panels()?;
-        self.render_rows()?;
-        self.render_time()?;
+        let mut y = 0;
+        let heights = self.element_heights(self.canvas.viewport().height());
+        for (i, height) in heights.into_iter().enumerate() {
+            match self.layout[i] {
+                Element::Title(idx) => self.render_title(idx, y)?,
+                Element::Splits { .. } => self.render_rows(y)?,
+                Element::Timer(idx) => render_timer(
+                    &mut self.canvas,
+                    &self.timers[idx],
+                    &self.time_str,
+                    y + height as i32,
+                )?,
+                Element::SegmentTimer(idx) => self.render_segment(idx, y, height)?,
+                Element::Panel(idx) => self.render_panel(idx, y)?,
+                Element::Separator => {
+                    let width = self.canvas.viewport().width() as i32;
+                    self.canvas.set_draw_color(sdl_color(self.colors.separator));
+                    self.canvas
+                        .draw_line(Point::new(0, y + 1), Point::new(width, y + 1))?;
+                }
+                Element::Spacer(_) => {}
+            }
+            y += height as i32;
+        }
+        self.render_error()?;
         self.canvas.present();
         Ok(())
     }
@@ -555,12 +604,17 @@ impl<'a, 'b> RenderState<'a, 'b> {
         self.splits = vec![];
         for (idx, name) in self.run.borrow().splits().iter().enumerate() {
             self.splits.push(Split::new(
-                render_text(name, &self.splits_font, &self.creator, Color::WHITE)?,
+                render_text(
+                    name,
+                    &self.splits_font,
+                    &self.creator,
+                    sdl_color(self.colors.text),
+                )?,
                 render_text(
                     &string_times[idx],
                     &self.splits_font,
                     &self.creator,
-                    Color::WHITE,
+                    sdl_color(self.colors.text),
                 )?,
                 None,
                 None,
@@ -575,24 +629,285 @@ impl<'a, 'b> RenderState<'a, 'b> {
         self.highlighted = usize::MAX;
         self.current = 0;
         self.status = SplitStatus::None;
-        let max_initial_splits = ((self.canvas.viewport().height() - self.timer_height)
-            / ((self.splits_height * (1 + !self.inline as u32)) + 5))
-            as usize;
-        if self.splits.is_empty() {
-            self.max_splits = 0;
-            self.bottom_index = 0;
-        } else if max_initial_splits > self.splits.len() {
-            self.max_splits = self.splits.len();
-            self.bottom_index = self.splits.len() - 1;
-        } else {
-            self.max_splits = max_initial_splits;
-            self.bottom_index = max_initial_splits - 1;
+        self.segment_str = "0.000".into();
+        self.fit_splits(self.canvas.viewport().height());
+        self.update_titles()?;
+        self.update_segments()?;
+        self.update_possible(0, 0)
+    }
+
+    // show an error over the splits until the next successful reload
+    pub fn show_error(&mut self, msg: &str) -> Result<(), String> {
+        let width = self.canvas.viewport().width().saturating_sub(10).max(1);
+        let sur = self
+            .splits_font
+            .render(msg)
+            .blended_wrapped(Color::WHITE, width)
+            .map_err(|_| get_error())?;
+        self.error = Some(
+            self.creator
+                .create_texture_from_surface(sur)
+                .map_err(|_| get_error())?,
+        );
+        Ok(())
+    }
+
+    pub fn clear_error(&mut self) {
+        self.error = None;
+    }
+
+    // override the layout option from the config
+    pub fn set_inline(&mut self, inline: bool) -> Result<(), String> {
+        self.inline = inline;
+        self.fit_splits(self.canvas.viewport().height());
+        self.update_highlighted();
+        Ok(())
+    }
+
+    // restyle without losing the timing method, comparison or the attempt in progress
+    pub fn reload_config(mut self, config: &Config) -> Result<Self, String> {
+        // the attempt's textures belong to the canvas, which moves over to the new state
+        let attempt: Vec<_> = self.splits.iter_mut().map(Split::take_times).collect();
+        let (method, comparison, is_running, current) =
+            (self.method, self.comparison, self.is_running, self.current);
+        let (split_pb, split_gold) = (self.split_pb, self.split_gold);
+        let (time_str, segment_str) = (self.time_str, self.segment_str);
+        let mut state = Self::new(self.run, self.canvas, config)?;
+        for (split, (cur, diff)) in state.splits.iter_mut().zip(attempt) {
+            split.set_cur(cur);
+            split.set_diff(diff);
+        }
+        state.method = method;
+        state.comparison = comparison;
+        state.is_running = is_running;
+        state.current = current;
+        state.split_pb = split_pb;
+        state.split_gold = split_gold;
+        state.time_str = time_str;
+        state.segment_str = segment_str;
+        state.update_comparison()?;
+        state.update_sob()?;
+        state.update_segments()?;
+        state.update_possible(0, 0)?;
+        state.fit_splits(state.canvas.viewport().height());
+        state.update_highlighted();
+        Ok(state)
+    }
+
+    fn update_comparison(&mut self) -> Result<(), String> {
+        let segments =
+            analysis::comparison_segments(&self.run.borrow(), self.method, self.comparison);
+        for (split, &time) in self
+            .splits
+            .iter_mut()
+            .zip(&format::split_time_sum(&segments))
+        {
+            let text = if time == 0 {
+                "-  ".into()
+            } else {
+                format::split_time_text(time)
+            };
+            split.set_comp(render_text(
+                text,
+                &self.splits_font,
+                &self.creator,
+                sdl_color(self.colors.text),
+            )?);
+        }
+        Ok(())
+    }
+
+    fn update_sob(&mut self) -> Result<(), String> {
+        for panel in &mut self.panels {
+            if *panel.panel_type() == Panel::SumOfBest {
+                panel.set_time(render_text(
+                    format::split_time_text(analysis::sum_of_best(&self.run.borrow(), self.method)),
+                    &self.splits_font,
+                    &self.creator,
+                    sdl_color(self.colors.panel_value),
+                )?);
+            }
         }
         Ok(())
     }
 
-    pub fn reload_config(self, config: &Config) -> Result<Self, String> {
-        Self::new(self.run, self.canvas, config)
+    fn update_titles(&mut self) -> Result<(), String> {
+        let run = self.run.borrow();
+        let color = sdl_color(self.colors.text);
+        for title in &mut self.titles {
+            let game = if run.game_title().is_empty() {
+                None
+            } else {
+                Some(render_text(
+                    run.game_title(),
+                    &title.font,
+                    &self.creator,
+                    color,
+                )?)
+            };
+            let category = if run.category().is_empty() {
+                None
+            } else {
+                Some(render_text(
+                    run.category(),
+                    &title.font,
+                    &self.creator,
+                    color,
+                )?)
+            };
+            let count = if title.attempts {
+                let count = format!("{}/{}", run.finished_count(), run.attempt_count());
+                Some(render_text(count, &title.font, &self.creator, color)?)
+            } else {
+                None
+            };
+            set_texture(&mut title.game, game);
+            set_texture(&mut title.category, category);
+            set_texture(&mut title.count, count);
+        }
+        Ok(())
+    }
+
+    fn update_segments(&mut self) -> Result<(), String> {
+        let run = self.run.borrow();
+        let color = sdl_color(self.colors.panel_label);
+        let current = self.current;
+        let segment_text = |label: &str, times: &[u128]| match times.get(current) {
+            Some(&t) if t != 0 => format!("{}: {}", label, format::split_time_text(t)),
+            _ => format!("{}: -", label),
+        };
+        for segment in &mut self.segments {
+            for tex in segment.times.drain(..) {
+                destroy_texture(&tex);
+            }
+            if segment.pb {
+                let text = segment_text("PB", run.pb_times_for(self.method));
+                segment
+                    .times
+                    .push(render_text(text, &self.splits_font, &self.creator, color)?);
+            }
+            if segment.best {
+                let text = segment_text("Best", run.gold_times_for(self.method));
+                segment
+                    .times
+                    .push(render_text(text, &self.splits_font, &self.creator, color)?);
+            }
+        }
+        Ok(())
+    }
+
+    // update the panels that depend on which split is being run
+    fn update_possible(&mut self, time: u128, split_time: u128) -> Result<(), String> {
+        let run = self.run.borrow();
+        for panel in &mut self.panels {
+            let text = match *panel.panel_type() {
+                Panel::BestPossibleTime => format::split_time_text(analysis::best_possible_time(
+                    &run,
+                    self.method,
+                    self.current,
+                    time,
+                    split_time,
+                )),
+                Panel::PossibleTimeSave { total: false } => {
+                    match analysis::possible_time_save(&run, self.method, self.current) {
+                        Some(save) => format::split_time_text(save),
+                        None => "-  ".into(),
+                    }
+                }
+                Panel::PossibleTimeSave { total: true } => format::split_time_text(
+                    analysis::total_possible_time_save(&run, self.method, self.current),
+                ),
+                _ => continue,
+            };
+            panel.set_time(render_text(
+                text,
+                &self.splits_font,
+                &self.creator,
+                sdl_color(self.colors.panel_value),
+            )?);
+        }
+        Ok(())
+    }
+
+    fn update_attempts(&mut self) -> Result<(), String> {
+        self.update_titles()?;
+        for panel in &mut self.panels {
+            if *panel.panel_type() == Panel::Attempts {
+                panel.set_time(render_text(
+                    self.run.borrow().attempt_count(),
+                    &self.splits_font,
+                    &self.creator,
+                    sdl_color(self.colors.panel_value),
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    fn row_height(&self) -> u32 {
+        self.splits_height * (1 + !self.inline as u32) + 5
+    }
+
+    // the height of an element, or None for a list of splits that fills the space left by the rest
+    fn element_height(&self, element: &Element) -> Option<u32> {
+        match *element {
+            Element::Splits { visible } => visible.map(|n| n as u32 * self.row_height()),
+            Element::Title(idx) => Some(self.titles[idx].height * 2),
+            Element::Timer(idx) => Some(self.timers[idx].height),
+            Element::SegmentTimer(idx) => {
+                let segment = &self.segments[idx];
+                let lines = segment.pb as u32 + segment.best as u32;
+                Some(segment.timer.height.max(lines * self.splits_height))
+            }
+            Element::Panel(_) => Some(self.splits_height),
+            Element::Separator => Some(SEPARATOR_HEIGHT),
+            Element::Spacer(height) => Some(height),
+        }
+    }
+
+    fn fixed_height(&self) -> u32 {
+        self.layout
+            .iter()
+            .filter_map(|e| self.element_height(e))
+            .sum()
+    }
+
+    fn element_heights(&self, window_height: u32) -> Vec<u32> {
+        let left = window_height.saturating_sub(self.fixed_height());
+        self.layout
+            .iter()
+            .map(|e| self.element_height(e).unwrap_or(left))
+            .collect()
+    }
+
+    // show as many splits as fit in the window, keeping the current split on screen during a run
+    fn fit_splits(&mut self, window_height: u32) {
+        let heights = self.element_heights(window_height);
+        let rows = self
+            .layout
+            .iter()
+            .zip(heights)
+            .find_map(|(e, height)| match *e {
+                Element::Splits { visible: Some(n) } => Some(n),
+                Element::Splits { visible: None } => Some((height / self.row_height()) as usize),
+                _ => None,
+            })
+            .unwrap_or(0)
+            .min(self.splits.len());
+        self.max_splits = rows;
+        if rows == 0 {
+            self.top_index = 0;
+            self.bottom_index = 0;
+        } else {
+            if self.is_running && self.current >= self.top_index + rows {
+                self.top_index = self.current + 1 - rows;
+            } else if self.is_running && self.current < self.top_index {
+                self.top_index = self.current;
+            }
+            self.top_index = self.top_index.min(self.splits.len() - rows);
+            self.bottom_index = self.top_index + rows - 1;
+        }
+        self.update_highlighted();
     }
 
     fn update_highlighted(&mut self) {
@@ -603,14 +918,13 @@ impl<'a, 'b> RenderState<'a, 'b> {
         }
     }
 
-    fn render_rows(&mut self) -> Result<(), String> {
+    fn render_rows(&mut self, mut y: i32) -> Result<(), String> {
         let on_screen = if self.max_splits > 0 {
             &self.splits[self.top_index..=self.bottom_index]
         } else {
             &[]
         };
         let incr_height: i32 = (self.splits_height * (!self.inline as u32 + 1)) as i32;
-        let mut y = 0;
         let mut row: Rect;
         let window_width = self.canvas.viewport().width();
         // draw each split name on the left of the screen
@@ -618,7 +932,7 @@ impl<'a, 'b> RenderState<'a, 'b> {
             let TextureQuery { width, height, .. } = item.name().query();
             // draw the blue highlight box before drawing the text for the split with index current
             if index == self.highlighted {
-                self.canvas.set_draw_color(Color::BLUE);
+                self.canvas.set_draw_color(sdl_color(self.colors.highlight));
                 self.canvas
                     .fill_rect(Rect::new(0, y - 1, window_width, incr_height as u32 + 5))?;
             }
@@ -671,7 +985,7 @@ impl<'a, 'b> RenderState<'a, 'b> {
                 );
                 self.canvas.copy(x, None, Some(row))?;
             }
-            self.canvas.set_draw_color(Color::GRAY);
+            self.canvas.set_draw_color(sdl_color(self.colors.separator));
             // draw a line to separate between the rows
             y += incr_height + 3;
             self.canvas
@@ -681,67 +995,101 @@ impl<'a, 'b> RenderState<'a, 'b> {
         Ok(())
     }
 
-    fn render_time(&mut self) -> Result<(), String> {
-        let coords = self.map.gen_str_coords(&self.time_str);
-        let vp = self.canvas.viewport();
-        let h = vp.height();
-        let w = vp.width();
-        let font_y = self.timer_height;
-        let mut src = Rect::new(0, 0, 0, font_y);
-        // multiply initial values by 8/10 so that the font is smaller
-        let mut dst = Rect::new(
-            0,
-            (h - (font_y * 8 / 10) - (self.splits_height * self.panels.len() as u32)) as i32 - 5,
-            0,
-            font_y * 8 / 10,
-        );
-        for (idx, (sx, sw, dx, dw)) in coords.iter().enumerate() {
-            src.set_x((*sx).try_into().unwrap());
-            src.set_width(*sw);
-            let wdx: i32 = if w < *dx {
-                let tmp: i32 = (*dx - w).try_into().unwrap();
-                -tmp
-            } else {
-                (w - *dx).try_into().unwrap()
-            };
-            dst.set_x(wdx);
-            dst.set_width(*dw);
-            if idx == 3 {
-                dst.set_y((h - font_y - (self.splits_height * self.panels.len() as u32)) as i32);
-                dst.set_height(font_y);
-            }
-            self.canvas.copy(&self.map.tex, Some(src), Some(dst))?;
-        }
-        Ok(())
-    }
-
-    fn render_panels(&mut self) -> Result<(), String> {
-        let mut num = 1;
-        for panel in &self.panels {
-            let TextureQuery { width, height, .. } = panel.text().query();
-            self.canvas.copy(
-                panel.text(),
-                None,
-                Some(Rect::new(
-                    0,
-                    (self.canvas.viewport().height() - (num * height)) as i32,
-                    width,
-                    height,
-                )),
-            )?;
-            let TextureQuery { width, height, .. } = panel.time().query();
+    // draw the game on the first line and the category and attempt count on the second
+    fn render_title(&mut self, idx: usize, y: i32) -> Result<(), String> {
+        let title = &self.titles[idx];
+        let width = self.canvas.viewport().width() as i32;
+        let mut right = width;
+        if let Some(tex) = &title.count {
+            let TextureQuery {
+                width: w, height, ..
+            } = tex.query();
+            right -= w as i32 + 10;
             self.canvas.copy(
-                panel.time(),
+                tex,
                 None,
                 Some(Rect::new(
-                    (self.canvas.viewport().width() - width) as i32,
-                    (self.canvas.viewport().height() - (num * height)) as i32,
-                    width,
+                    width - w as i32,
+                    y + title.height as i32,
+                    w,
                     height,
                 )),
             )?;
-            num += 1;
         }
+        for (line, tex) in [&title.game, &title.category].iter().enumerate() {
+            if let Some(tex) = tex {
+                let TextureQuery {
+                    width: w, height, ..
+                } = tex.query();
+                // only the category shares its line with the count
+                let right = if line == 0 { width } else { right };
+                let x = match title.align {
+                    Align::Left => 0,
+                    Align::Center => ((width - w as i32) / 2).min(right - w as i32),
+                    Align::Right => right - w as i32,
+                };
+                let line_y = y + (line as u32 * title.height) as i32;
+                self.canvas
+                    .copy(tex, None, Some(Rect::new(x, line_y, w, height)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_segment(&mut self, idx: usize, y: i32, height: u32) -> Result<(), String> {
+        let segment = &self.segments[idx];
+        render_timer(
+            &mut self.canvas,
+            &segment.timer,
+            &self.segment_str,
+            y + height as i32,
+        )?;
+        let width = self.canvas.viewport().width();
+        let mut line_y = y;
+        for tex in &segment.times {
+            let TextureQuery {
+                width: w, height, ..
+            } = tex.query();
+            let x = if segment.timer.align == Align::Left {
+                (width - w) as i32
+            } else {
+                0
+            };
+            self.canvas
+                .copy(tex, None, Some(Rect::new(x, line_y, w, height)))?;
+            line_y += height as i32;
+        }
+        Ok(())
+    }
+
+    fn render_error(&mut self) -> Result<(), String> {
+        if let Some(tex) = &self.error {
+            let TextureQuery { width, height, .. } = tex.query();
+            self.canvas.set_draw_color(Color::RGB(128, 0, 0));
+            self.canvas
+                .fill_rect(Rect::new(0, 0, self.canvas.viewport().width(), height + 10))?;
+            self.canvas
+                .copy(tex, None, Some(Rect::new(5, 5, width, height)))?;
+        }
+        Ok(())
+    }
+
+    fn render_panel(&mut self, idx: usize, y: i32) -> Result<(), String> {
+        let panel = &self.panels[idx];
+        let TextureQuery { width, height, .. } = panel.text().query();
+        self.canvas
+            .copy(panel.text(), None, Some(Rect::new(0, y, width, height)))?;
+        let TextureQuery { width, height, .. } = panel.time().query();
+        self.canvas.copy(
+            panel.time(),
+            None,
+            Some(Rect::new(
+                (self.canvas.viewport().width() - width) as i32,
+                y,
+                width,
+                height,
+            )),
+        )?;
         Ok(())
     }
 
@@ -776,12 +1124,11 @@ impl FontMap {
             .render("- 0 1 2 3 4 5 6 7 8 9 : .")
             .blended(color)
             .map_err(|_| get_error())?;
-        Ok(Self {
-            tex: creator
-                .create_texture_from_surface(&surface)
-                .map_err(|_| get_error())?,
-            coords,
-        })
+        let mut tex = creator
+            .create_texture_from_surface(&surface)
+            .map_err(|_| get_error())?;
+        tex.set_alpha_mod(color.a);
+        Ok(Self { tex, coords })
     }
 
     fn gen_str_coords(&self, string: &str) -> Vec<(u32, u32, u32, u32)> {
@@ -833,15 +1180,15 @@ impl Background {
         viewport: Rect,
         creator: &TextureCreator<WindowContext>,
     ) -> Result<Self, String> {
-        let bg: Option<Surface> = match config.img() {
-            Some(p) => Some(Surface::from_file(&p)?),
+        let bg: Option<Surface> = match config.theme().img() {
+            Some(p) => Some(Surface::from_file(mist_core::config::paths::resolve(p))?),
             None => None,
         };
         if let Some(x) = bg {
             let bg_tex: Texture;
             let width = viewport.width();
             let height = viewport.height();
-            if !config.img_scaled() {
+            if !config.theme().img_scaled() {
                 let mut sur = Surface::new(width, height, PixelFormatEnum::RGB24)?;
                 let cutoffx = {
                     if x.width() > width {
@@ -903,7 +1250,115 @@ fn render_text<T: ToString>(
         .render(&text.to_string())
         .blended(color)
         .map_err(|_| get_error())?;
-    creator
+    let mut tex = creator
         .create_texture_from_surface(sur)
-        .map_err(|_| get_error())
+        .map_err(|_| get_error())?;
+    tex.set_alpha_mod(color.a);
+    Ok(tex)
+}
+
+fn new_panel(
+    panel: Panel,
+    run: &RefCell<Run>,
+    font: &Font<'_, '_>,
+    creator: &TextureCreator<WindowContext>,
+    colors: ColorScheme,
+) -> Result<RenderPanel, String> {
+    let text = match panel {
+        Panel::Pace { golds } => {
+            if golds {
+                "Pace (best)"
+            } else {
+                "Pace (pb)"
+            }
+        }
+        Panel::SumOfBest => "Sum of Best",
+        Panel::CurrentSplitDiff { golds } => {
+            if golds {
+                "Split (best)"
+            } else {
+                "Split (pb)"
+            }
+        }
+        Panel::Attempts => "Attempts",
+        Panel::BestPossibleTime => "Best Possible Time",
+        Panel::PossibleTimeSave { total } => {
+            if total {
+                "Total Possible Save"
+            } else {
+                "Possible Time Save"
+            }
+        }
+        Panel::PreviousSegment { golds } => {
+            if golds {
+                "Prev. Segment (best)"
+            } else {
+                "Prev. Segment (pb)"
+            }
+        }
+    };
+    let time = match panel {
+        Panel::SumOfBest => {
+            format::split_time_text(analysis::sum_of_best(&run.borrow(), TimingMethod::RealTime))
+        }
+        Panel::Attempts => run.borrow().attempt_count().to_string(),
+        _ => "-  ".into(),
+    };
+    let time_tex = render_text(&time, font, creator, sdl_color(colors.panel_value))?;
+    let text_tex = render_text(text, font, creator, sdl_color(colors.panel_label))?;
+    Ok(RenderPanel::new(text_tex, time_tex, panel))
+}
+
+// draw a timer with its bottom edge at `bottom`
+fn render_timer(
+    canvas: &mut WindowCanvas,
+    timer: &TimerDisplay<'_, '_>,
+    text: &str,
+    bottom: i32,
+) -> Result<(), String> {
+    let coords = timer.map.gen_str_coords(text);
+    let w = canvas.viewport().width() as i32;
+    let font_y = timer.height;
+    // characters are placed by their distance from the right edge of the text, so the last one is the widest
+    let text_width = coords.last().map_or(0, |c| c.2 as i32);
+    let right = match timer.align {
+        Align::Left => text_width,
+        Align::Center => (w + text_width) / 2,
+        Align::Right => w,
+    };
+    let mut src = Rect::new(0, 0, 0, font_y);
+    // multiply initial values by 8/10 so that the font is smaller
+    let mut dst = Rect::new(0, bottom - (font_y * 8 / 10) as i32 - 5, 0, font_y * 8 / 10);
+    for (idx, (sx, sw, dx, dw)) in coords.iter().enumerate() {
+        src.set_x(*sx as i32);
+        src.set_width(*sw);
+        dst.set_x(right - *dx as i32);
+        dst.set_width(*dw);
+        if idx == 3 {
+            dst.set_y(bottom - font_y as i32);
+            dst.set_height(font_y);
+        }
+        canvas.copy(&timer.map.tex, Some(src), Some(dst))?;
+    }
+    Ok(())
+}
+
+// textures have to be freed by hand
+fn destroy_texture(tex: &Texture) {
+    unsafe {
+        sdl2::sys::SDL_DestroyTexture(tex.raw());
+    }
+}
+
+// replace a texture that may not exist, destroying the old one
+fn set_texture(slot: &mut Option<Texture>, tex: Option<Texture>) {
+    if let Some(old) = slot.as_ref() {
+        destroy_texture(old);
+    }
+    *slot = tex;
+}
+
+fn sdl_color(color: config::Color) -> Color {
+    let (r, g, b, a) = color.into();
+    Color::RGBA(r, g, b, a)
 }
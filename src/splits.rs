@@ -33,6 +33,10 @@ impl Split {
     pub fn diff(&self) -> &Option<Texture> {
         &self.diff
     }
+    // hand over the attempt's textures without destroying them
+    pub fn take_times(&mut self) -> (Option<Texture>, Option<Texture>) {
+        (self.current.take(), self.diff.take())
+    }
     // Have to destroy the textures or else we will eat all the memory.
     // No setting textures after the canvas is dead, I guess? Not that there's any reason to do that anyway...
     pub fn set_comp(&mut self, tex: Texture) {
@@ -0,0 +1,48 @@
+// notice when a file is changed on disk so it can be reloaded
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// how often the file's modification time is checked
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_owned();
+        Self {
+            modified: modified(&path),
+            path,
+            last_check: Instant::now(),
+        }
+    }
+
+    // start watching a different file, without treating it as changed
+    pub fn set_path<P: AsRef<Path>>(&mut self, path: P) {
+        *self = Self::new(path);
+    }
+
+    // returns true once for each time the file changes
+    // a file that is removed isn't a change, so that editors that replace files don't cause a reload of nothing
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        match modified(&self.path) {
+            Some(m) if Some(m) != self.modified => {
+                self.modified = Some(m);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}